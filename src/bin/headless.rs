@@ -0,0 +1,108 @@
+//! Runs a scene without a window and writes the state of every body to a CSV file.
+//!
+//! ```text
+//! headless [--scene <name or path>] [--steps <n>] [--every <k>] [--output <path>]
+//! ```
+//!
+//! The state is written before the first step and after every `k`-th step, one row per body.
+
+use rigid_body_physics_engine::scene;
+use rigid_body_physics_engine::world::World;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::time::Instant;
+
+struct Options {
+    scene: String,
+    steps: usize,
+    every: usize,
+    output: String,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let invalid = |msg: String| Error::new(ErrorKind::InvalidInput, msg);
+        let mut options = Options {
+            scene: "demo".to_string(),
+            steps: 600,
+            every: 1,
+            output: "state.csv".to_string(),
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| invalid(format!("{flag} needs a value")))?;
+            let number = |value: &str| {
+                value
+                    .parse::<usize>()
+                    .map_err(|e| invalid(format!("invalid {flag} `{value}`: {e}")))
+            };
+            match flag.as_str() {
+                "--scene" => options.scene = value,
+                "--steps" => options.steps = number(&value)?,
+                "--every" => options.every = number(&value)?.max(1),
+                "--output" => options.output = value,
+                _ => return Err(invalid(format!("unknown flag {flag}"))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Appends one row per body with its state after `step` steps.
+fn write_state(out: &mut impl Write, world: &World, step: usize) -> Result<()> {
+    for (handle, body) in world.iter() {
+        let [px, py, pz] = [0, 1, 2].map(|i| body.position[i]);
+        let q = body.orientation;
+        let [vx, vy, vz] = [0, 1, 2].map(|i| body.linear_velocity[i]);
+        let [wx, wy, wz] = [0, 1, 2].map(|i| body.angular_velocity[i]);
+        writeln!(
+            out,
+            "{step},{:.6},{},{px},{py},{pz},{},{},{},{},{vx},{vy},{vz},{wx},{wy},{wz},{}",
+            world.time(),
+            handle.0,
+            q.w,
+            q.x,
+            q.y,
+            q.z,
+            body.is_sleeping() as u8,
+        )?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    let mut world = scene::open(&options.scene).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("unable to load scene {}: {e}", options.scene),
+        )
+    })?;
+
+    let mut out = BufWriter::new(File::create(&options.output)?);
+    writeln!(
+        out,
+        "step,time,body,px,py,pz,qw,qx,qy,qz,vx,vy,vz,wx,wy,wz,sleeping"
+    )?;
+    write_state(&mut out, &world, 0)?;
+    let start = Instant::now();
+    for step in 1..=options.steps {
+        world.step(world.timestep);
+        if step % options.every == 0 || step == options.steps {
+            write_state(&mut out, &world, step)?;
+        }
+    }
+    out.flush()?;
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{}: {} steps in {:.3} s ({:.3} ms per step), energy {:.3} J, state written to {}",
+        options.scene,
+        options.steps,
+        elapsed,
+        1000.0 * elapsed / options.steps.max(1) as f64,
+        world.total_energy(),
+        options.output
+    );
+    Ok(())
+}
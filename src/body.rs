@@ -0,0 +1,230 @@
+use crate::broadphase::Aabb;
+use crate::collider::Collider;
+use crate::material::Material;
+use crate::math::{Mat3, Quat, Transform, Vec3};
+use crate::shape::Shape;
+use crate::stl::IndexedMesh;
+use crate::trimesh::TriMeshCollider;
+use std::io::Result;
+use std::sync::Arc;
+
+/// Vertices around curved primitives in the meshes of [RigidBody::from_shape].
+const SHAPE_SEGMENTS: usize = 24;
+
+fn check_density(density: f32) -> Result<()> {
+    if density.is_finite() && density > 0.0 {
+        return Ok(());
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("density must be positive and finite, got {}", density),
+    ))
+}
+
+/// A simulated rigid body.
+///
+/// The mesh is shared so that many bodies can be created from one imported STL without copying
+/// its vertex data.
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+    /// World space position of the body origin.
+    pub position: Vec3<f32>,
+    /// Orientation as a unit quaternion.
+    pub orientation: Quat<f32>,
+    /// Linear velocity in world space.
+    pub linear_velocity: Vec3<f32>,
+    /// Angular velocity in world space, in radians per second.
+    pub angular_velocity: Vec3<f32>,
+    /// Mass of the body, `f32::INFINITY` for static bodies.
+    pub mass: f32,
+    /// Inverse of the mass, zero for static bodies.
+    pub inv_mass: f32,
+    /// Inertia tensor in body space, zero for static bodies.
+    pub inertia: Mat3<f32>,
+    /// Inverse inertia tensor in body space, zero for static bodies.
+    pub inv_inertia: Mat3<f32>,
+    /// Force accumulated for the next step, cleared after every step.
+    pub force: Vec3<f32>,
+    /// Torque accumulated for the next step, cleared after every step.
+    pub torque: Vec3<f32>,
+    /// Mesh the body was built from, in body space.
+    pub mesh: Arc<IndexedMesh>,
+    /// Shape used for collisions, in body space. Defaults to the convex hull of `mesh`.
+    pub collider: Collider,
+    /// Friction and restitution of the surface.
+    pub material: Material,
+    /// Sleeping bodies are skipped by the simulation until something touches them or a force is
+    /// applied.
+    pub sleeping: bool,
+    /// Sweeps the body through its motion in every step so that it can not pass through thin
+    /// shapes when it is fast, see [World::ccd_threshold](crate::world::World::ccd_threshold).
+    pub ccd: bool,
+    /// How long the body has been resting, see [SleepParams](crate::island::SleepParams).
+    pub sleep_time: f32,
+}
+
+impl RigidBody {
+    /// Creates a dynamic body from a closed mesh of uniform `density`.
+    ///
+    /// The mesh is re-centered on its center of mass, and the body is placed where the center of
+    /// mass was so that it keeps the position it had in the mesh file.
+    pub fn new(mesh: Arc<IndexedMesh>, density: f32) -> Result<Self> {
+        check_density(density)?;
+        let props = mesh.mass_properties(density)?;
+        let com = props.center_of_mass;
+        let mesh = if com == Vec3::ZERO {
+            mesh
+        } else {
+            let mut centered = IndexedMesh::clone(&mesh);
+            centered.translate(-com);
+            Arc::new(centered)
+        };
+        Ok(Self {
+            position: com,
+            mass: props.mass,
+            inv_mass: 1.0 / props.mass,
+            inertia: props.inertia,
+            inv_inertia: props.inv_inertia(),
+            ..Self::new_static(mesh)
+        })
+    }
+
+    /// Creates a dynamic body from an owned mesh, see [RigidBody::new].
+    pub fn from_mesh(mesh: IndexedMesh, density: f32) -> Result<Self> {
+        Self::new(Arc::new(mesh), density)
+    }
+
+    /// Creates a dynamic body at the origin from a primitive of uniform `density`. Its mesh is the
+    /// tessellated shape.
+    pub fn from_shape(shape: Shape, density: f32) -> Result<Self> {
+        check_density(density)?;
+        let props = shape.mass_properties(density)?;
+        let mesh = Arc::new(shape.to_mesh(SHAPE_SEGMENTS));
+        Ok(Self {
+            mass: props.mass,
+            inv_mass: 1.0 / props.mass,
+            inertia: props.inertia,
+            inv_inertia: props.inv_inertia(),
+            ..Self::new_static(mesh).with_collider(Collider::Primitive(shape))
+        })
+    }
+
+    /// Creates an immovable body at the origin, useful for floors and fixtures.
+    pub fn new_static(mesh: Arc<IndexedMesh>) -> Self {
+        Self {
+            position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            linear_velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            mass: f32::INFINITY,
+            inv_mass: 0.0,
+            inertia: Mat3::ZERO,
+            inv_inertia: Mat3::ZERO,
+            force: Vec3::ZERO,
+            torque: Vec3::ZERO,
+            collider: Collider::Convex(mesh.clone()),
+            material: Material::default(),
+            sleeping: false,
+            ccd: false,
+            sleep_time: 0.0,
+            mesh,
+        }
+    }
+
+    /// Creates an immovable body at the origin from a primitive, for example a
+    /// [Plane](Shape::Plane) as the ground.
+    pub fn new_static_shape(shape: Shape) -> Self {
+        Self::new_static(Arc::new(shape.to_mesh(SHAPE_SEGMENTS)))
+            .with_collider(Collider::Primitive(shape))
+    }
+
+    /// Creates an immovable body at the origin that collides with the exact triangles of `mesh`,
+    /// for example a fixture or terrain loaded from STL.
+    pub fn new_trimesh(mesh: Arc<IndexedMesh>) -> Self {
+        let collider = TriMeshCollider::new(mesh.clone());
+        Self::new_static(mesh).with_collider(Collider::TriMesh(Arc::new(collider)))
+    }
+
+    /// Replaces the collision shape, which has to be given in body space.
+    pub fn with_collider(mut self, collider: Collider) -> Self {
+        self.collider = collider;
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Returns true if the body is not moved by the simulation.
+    pub fn is_static(&self) -> bool {
+        self.inv_mass == 0.0
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Puts the body back into the simulation. Other bodies of its island follow in the next
+    /// step.
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_time = 0.0;
+    }
+
+    /// Body to world transform.
+    pub fn transform(&self) -> Transform<f32> {
+        Transform::new(self.position, self.orientation)
+    }
+
+    /// World space bounding box of the collider.
+    pub fn aabb(&self) -> Aabb {
+        self.collider.aabb(&self.transform())
+    }
+
+    /// Inverse inertia tensor rotated into world space.
+    pub fn world_inv_inertia(&self) -> Mat3<f32> {
+        let r = self.orientation.to_mat3();
+        r * self.inv_inertia * r.transpose()
+    }
+
+    /// Inertia tensor rotated into world space.
+    pub fn world_inertia(&self) -> Mat3<f32> {
+        let r = self.orientation.to_mat3();
+        r * self.inertia * r.transpose()
+    }
+
+    /// Applies a force through the center of mass for the next step.
+    pub fn apply_force(&mut self, force: Vec3<f32>) {
+        self.wake_up();
+        self.force += force;
+    }
+
+    /// Applies a force at world space point `p` for the next step.
+    pub fn apply_force_at(&mut self, force: Vec3<f32>, p: Vec3<f32>) {
+        self.wake_up();
+        self.force += force;
+        self.torque += (p - self.position).cross(force);
+    }
+
+    /// Applies a torque for the next step.
+    pub fn apply_torque(&mut self, torque: Vec3<f32>) {
+        self.wake_up();
+        self.torque += torque;
+    }
+
+    /// Translational plus rotational kinetic energy.
+    pub fn kinetic_energy(&self) -> f32 {
+        if self.is_static() {
+            return 0.0;
+        }
+        let w = self.angular_velocity;
+        0.5 * self.mass * self.linear_velocity.length_squared()
+            + 0.5 * w.dot(self.world_inertia() * w)
+    }
+
+    /// Velocity of the body at world space point `p`.
+    pub fn velocity_at(&self, p: Vec3<f32>) -> Vec3<f32> {
+        self.linear_velocity + self.angular_velocity.cross(p - self.position)
+    }
+}
@@ -0,0 +1,511 @@
+use crate::math::{Transform, Vec3};
+use crate::world::BodyHandle;
+
+/// Axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3<f32>,
+    pub max: Vec3<f32>,
+}
+
+impl Aabb {
+    /// The box that contains nothing, neutral element of [Aabb::union].
+    pub const EMPTY: Self = Self {
+        min: Vec3::new([f32::INFINITY; 3]),
+        max: Vec3::new([f32::NEG_INFINITY; 3]),
+    };
+
+    pub fn new(min: Vec3<f32>, max: Vec3<f32>) -> Self {
+        Self { min, max }
+    }
+    /// Returns true if the box contains nothing, like [Aabb::EMPTY].
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::broadphase::Aabb;
+    /// use rigid_body_physics_engine::math::Vec3;
+    /// assert!(Aabb::EMPTY.is_empty());
+    /// assert!(!Aabb::from_points([Vec3::ZERO]).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+    /// Smallest box containing all `points`, [Aabb::EMPTY] if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3<f32>>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, p| Self {
+            min: aabb.min.min(p),
+            max: aabb.max.max(p),
+        })
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+    pub fn overlaps(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+    pub fn contains(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= other.min[i] && other.max[i] <= self.max[i])
+    }
+    pub fn contains_point(&self, p: Vec3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+    /// Grows the box by `margin` on every side.
+    pub fn fatten(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec3::splat(margin),
+            max: self.max + Vec3::splat(margin),
+        }
+    }
+    /// Box around this box after moving it by `transform`.
+    pub fn transformed(&self, transform: &Transform<f32>) -> Self {
+        let center = transform.transform_point(self.center());
+        let r = transform.rotation.to_mat3().map(f32::abs);
+        let half = r * (self.extents() * 0.5);
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+    pub fn center(&self) -> Vec3<f32> {
+        (self.min + self.max) * 0.5
+    }
+    pub fn extents(&self) -> Vec3<f32> {
+        self.max - self.min
+    }
+    pub fn surface_area(&self) -> f32 {
+        let [x, y, z] = <[f32; 3]>::from(self.extents().max(Vec3::ZERO));
+        2.0 * (x * y + y * z + z * x)
+    }
+    /// Entry distance of the ray `origin + t * dir` for `t` in `[0, max_t]`.
+    pub fn ray_intersection(&self, origin: Vec3<f32>, dir: Vec3<f32>, max_t: f32) -> Option<f32> {
+        let (mut t0, mut t1) = (0.0f32, max_t);
+        for i in 0..3 {
+            let inv = 1.0 / dir[i];
+            let (mut near, mut far) = (
+                (self.min[i] - origin[i]) * inv,
+                (self.max[i] - origin[i]) * inv,
+            );
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            // NaN from 0 * inf means the ray runs inside the slab, which keeps the bounds.
+            t0 = if near > t0 { near } else { t0 };
+            t1 = if far < t1 { far } else { t1 };
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some(t0)
+    }
+}
+
+/// Finds pairs of bodies whose bounding boxes overlap, as candidates for the narrowphase.
+pub trait Broadphase {
+    /// Starts tracking a body.
+    fn insert(&mut self, id: BodyHandle, aabb: Aabb);
+    /// Stops tracking a body.
+    fn remove(&mut self, id: BodyHandle);
+    /// Tells the broadphase that a body moved.
+    fn update(&mut self, id: BodyHandle, aabb: Aabb);
+    /// All overlapping pairs with the smaller handle first, sorted and without duplicates.
+    fn pairs(&mut self) -> Vec<(BodyHandle, BodyHandle)>;
+}
+
+const NULL: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct Node {
+    aabb: Aabb,
+    parent: usize,
+    children: [usize; 2],
+    height: i32,
+    /// Body of a leaf, unused for inner nodes.
+    id: BodyHandle,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.children[0] == NULL
+    }
+}
+
+/// Bounding volume hierarchy that is updated incrementally as bodies move.
+///
+/// Leaves store boxes grown by `margin`, so a body only has to be re-inserted once it leaves its
+/// fat box instead of on every step.
+///
+/// ```
+/// use rigid_body_physics_engine::broadphase::{Aabb, Broadphase, DynamicAabbTree};
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::world::BodyHandle;
+/// let unit = |x: f32| Aabb::new(Vec3::new([x, 0.0, 0.0]), Vec3::new([x + 1.0, 1.0, 1.0]));
+/// let mut tree = DynamicAabbTree::new(0.1);
+/// tree.insert(BodyHandle(0), unit(0.0));
+/// tree.insert(BodyHandle(1), unit(0.5));
+/// tree.insert(BodyHandle(2), unit(5.0));
+/// assert_eq!(tree.pairs(), vec![(BodyHandle(0), BodyHandle(1))]);
+/// tree.update(BodyHandle(2), unit(1.1));
+/// assert_eq!(tree.pairs().len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DynamicAabbTree {
+    /// Amount every leaf box is grown by.
+    pub margin: f32,
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+    leaves: Vec<usize>,
+}
+
+impl Default for DynamicAabbTree {
+    fn default() -> Self {
+        Self::new(0.05)
+    }
+}
+
+impl DynamicAabbTree {
+    pub fn new(margin: f32) -> Self {
+        Self {
+            margin,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NULL,
+            leaves: Vec::new(),
+        }
+    }
+
+    /// Calls `f` with every body whose fat box overlaps `aabb`.
+    pub fn query(&self, aabb: &Aabb, mut f: impl FnMut(BodyHandle)) {
+        if self.root == NULL {
+            return;
+        }
+        let mut stack = vec![self.root];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !node.aabb.overlaps(aabb) {
+                continue;
+            }
+            if node.is_leaf() {
+                f(node.id);
+            } else {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    /// Levels of inner nodes above the deepest leaf, zero for a tree with at most one body.
+    ///
+    /// The tree stays balanced, also for bodies inserted in order:
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::broadphase::{Aabb, Broadphase, DynamicAabbTree};
+    /// use rigid_body_physics_engine::math::Vec3;
+    /// use rigid_body_physics_engine::world::BodyHandle;
+    /// let mut tree = DynamicAabbTree::new(0.05);
+    /// for i in 0..500 {
+    ///     let y = i as f32;
+    ///     tree.insert(BodyHandle(i), Aabb::new(Vec3::new([0.0, y, 0.0]), Vec3::new([1.0, y + 1.0, 1.0])));
+    /// }
+    /// assert!(tree.height() <= 12);
+    /// assert_eq!(tree.pairs().len(), 499);
+    /// ```
+    pub fn height(&self) -> usize {
+        match self.root {
+            NULL => 0,
+            root => self.nodes[root].height as usize,
+        }
+    }
+
+    /// Fat box of a body, if it is in the tree.
+    pub fn fat_aabb(&self, id: BodyHandle) -> Option<Aabb> {
+        match self.leaves.get(id.0) {
+            Some(&leaf) if leaf != NULL => Some(self.nodes[leaf].aabb),
+            _ => None,
+        }
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL;
+            return;
+        }
+
+        // Descend towards the child whose box grows the least.
+        let aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let area = node.aabb.surface_area();
+            let combined = node.aabb.union(&aabb).surface_area();
+            let cost = 2.0 * combined;
+            let inheritance = 2.0 * (combined - area);
+            let child_cost = |c: usize| {
+                let child = &self.nodes[c];
+                let grown = child.aabb.union(&aabb).surface_area();
+                if child.is_leaf() {
+                    grown + inheritance
+                } else {
+                    grown - child.aabb.surface_area() + inheritance
+                }
+            };
+            let [c0, c1] = node.children;
+            let (cost0, cost1) = (child_cost(c0), child_cost(c1));
+            if cost < cost0 && cost < cost1 {
+                break;
+            }
+            index = if cost0 < cost1 { c0 } else { c1 };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let parent = self.allocate(Node {
+            aabb: self.nodes[sibling].aabb.union(&aabb),
+            parent: old_parent,
+            children: [sibling, leaf],
+            height: self.nodes[sibling].height + 1,
+            id: BodyHandle(NULL),
+        });
+        self.nodes[sibling].parent = parent;
+        self.nodes[leaf].parent = parent;
+        if old_parent == NULL {
+            self.root = parent;
+        } else {
+            let children = &mut self.nodes[old_parent].children;
+            let slot = if children[0] == sibling { 0 } else { 1 };
+            children[slot] = parent;
+        }
+        self.refit(self.nodes[leaf].parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let [c0, c1] = self.nodes[parent].children;
+        let sibling = if c0 == leaf { c1 } else { c0 };
+        if grand_parent == NULL {
+            self.root = sibling;
+            self.nodes[sibling].parent = NULL;
+        } else {
+            let children = &mut self.nodes[grand_parent].children;
+            let slot = if children[0] == parent { 0 } else { 1 };
+            children[slot] = sibling;
+            self.nodes[sibling].parent = grand_parent;
+            self.refit(grand_parent);
+        }
+        self.free.push(parent);
+    }
+
+    /// Recomputes boxes and heights from `index` up to the root, rotating unbalanced nodes on
+    /// the way so that bodies inserted in order, like a stack, do not degrade the tree into a
+    /// list.
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL {
+            index = self.balance(index);
+            self.fit(index);
+            index = self.nodes[index].parent;
+        }
+    }
+
+    /// Recomputes the box and height of inner node `index` from its children.
+    fn fit(&mut self, index: usize) {
+        let [c0, c1] = self.nodes[index].children;
+        let aabb = self.nodes[c0].aabb.union(&self.nodes[c1].aabb);
+        let height = 1 + self.nodes[c0].height.max(self.nodes[c1].height);
+        let node = &mut self.nodes[index];
+        node.aabb = aabb;
+        node.height = height;
+    }
+
+    /// If the heights of the children of `a` differ by more than one, rotates the taller child
+    /// up in place of `a`. Returns the node now at the place of `a`.
+    fn balance(&mut self, a: usize) -> usize {
+        let [b, c] = self.nodes[a].children;
+        if self.nodes[a].is_leaf() {
+            return a;
+        }
+        let difference = self.nodes[c].height - self.nodes[b].height;
+        if difference > 1 {
+            self.rotate(a, c)
+        } else if difference < -1 {
+            self.rotate(a, b)
+        } else {
+            a
+        }
+    }
+
+    /// Moves child `up` of `a` in place of `a`. `up` keeps its taller child and `a` takes the
+    /// shorter one instead of `up`.
+    fn rotate(&mut self, a: usize, up: usize) -> usize {
+        let parent = self.nodes[a].parent;
+        self.nodes[up].parent = parent;
+        self.nodes[a].parent = up;
+        if parent == NULL {
+            self.root = up;
+        } else {
+            let children = &mut self.nodes[parent].children;
+            let slot = if children[0] == a { 0 } else { 1 };
+            children[slot] = up;
+        }
+
+        let [f, g] = self.nodes[up].children;
+        let (keep, give) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[up].children = [a, keep];
+        let children = &mut self.nodes[a].children;
+        let slot = if children[0] == up { 0 } else { 1 };
+        children[slot] = give;
+        self.nodes[give].parent = a;
+        self.fit(a);
+        self.fit(up);
+        up
+    }
+}
+
+impl Broadphase for DynamicAabbTree {
+    fn insert(&mut self, id: BodyHandle, aabb: Aabb) {
+        if self.fat_aabb(id).is_some() {
+            self.remove(id);
+        }
+        let leaf = self.allocate(Node {
+            aabb: aabb.fatten(self.margin),
+            parent: NULL,
+            children: [NULL; 2],
+            height: 0,
+            id,
+        });
+        if self.leaves.len() <= id.0 {
+            self.leaves.resize(id.0 + 1, NULL);
+        }
+        self.leaves[id.0] = leaf;
+        self.insert_leaf(leaf);
+    }
+
+    fn remove(&mut self, id: BodyHandle) {
+        if let Some(leaf) = self.leaves.get_mut(id.0) {
+            if *leaf != NULL {
+                let index = std::mem::replace(leaf, NULL);
+                self.remove_leaf(index);
+                self.free.push(index);
+            }
+        }
+    }
+
+    fn update(&mut self, id: BodyHandle, aabb: Aabb) {
+        match self.fat_aabb(id) {
+            Some(fat) if fat.contains(&aabb) => {}
+            _ => self.insert(id, aabb),
+        }
+    }
+
+    fn pairs(&mut self) -> Vec<(BodyHandle, BodyHandle)> {
+        let mut pairs = Vec::new();
+        for &leaf in self.leaves.iter().filter(|&&l| l != NULL) {
+            let Node { aabb, id, .. } = self.nodes[leaf];
+            self.query(&aabb, |other| {
+                if id < other {
+                    pairs.push((id, other));
+                }
+            });
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+/// Sorts box bounds along one axis and sweeps over them.
+///
+/// Simpler than [DynamicAabbTree] and fast for scenes where bodies are spread along the sweep
+/// axis.
+#[derive(Clone, Debug, Default)]
+pub struct SweepAndPrune {
+    /// Axis index the boxes are sorted along.
+    pub axis: usize,
+    boxes: Vec<Option<Aabb>>,
+    order: Vec<usize>,
+}
+
+impl SweepAndPrune {
+    pub fn new(axis: usize) -> Self {
+        assert!(axis < 3);
+        Self {
+            axis,
+            ..Self::default()
+        }
+    }
+}
+
+impl Broadphase for SweepAndPrune {
+    fn insert(&mut self, id: BodyHandle, aabb: Aabb) {
+        if self.boxes.len() <= id.0 {
+            self.boxes.resize(id.0 + 1, None);
+        }
+        if self.boxes[id.0].replace(aabb).is_none() {
+            self.order.push(id.0);
+        }
+    }
+
+    fn remove(&mut self, id: BodyHandle) {
+        if let Some(slot) = self.boxes.get_mut(id.0) {
+            if slot.take().is_some() {
+                self.order.retain(|&i| i != id.0);
+            }
+        }
+    }
+
+    fn update(&mut self, id: BodyHandle, aabb: Aabb) {
+        self.insert(id, aabb);
+    }
+
+    fn pairs(&mut self) -> Vec<(BodyHandle, BodyHandle)> {
+        let axis = self.axis;
+        let boxes = &self.boxes;
+        let min = |i: usize| boxes[i].map_or(f32::INFINITY, |b| b.min[axis]);
+        // Insertion sort, the order hardly changes between steps.
+        for i in 1..self.order.len() {
+            let mut j = i;
+            while j > 0 && min(self.order[j - 1]) > min(self.order[j]) {
+                self.order.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for (n, &i) in self.order.iter().enumerate() {
+            let a = boxes[i].unwrap();
+            for &j in &self.order[n + 1..] {
+                let b = boxes[j].unwrap();
+                if b.min[axis] > a.max[axis] {
+                    break;
+                }
+                if a.overlaps(&b) {
+                    pairs.push((BodyHandle(i.min(j)), BodyHandle(i.max(j))));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+}
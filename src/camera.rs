@@ -0,0 +1,159 @@
+use crate::broadphase::Aabb;
+use crate::math::Vec3;
+use crate::render::Camera;
+use std::f32::consts::FRAC_PI_2;
+
+/// How a [CameraController] moves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Circles around a target point, dragging rotates around it and zooming changes the distance.
+    #[default]
+    Orbit,
+    /// Moves freely, dragging turns the view and zooming changes the speed.
+    Fly,
+}
+
+/// Pitch stays this far away from straight up and down so that the view never flips.
+const PITCH_LIMIT: f32 = FRAC_PI_2 - 0.01;
+/// Factor per wheel step of [CameraController::zoom].
+const ZOOM_STEP: f32 = 0.9;
+
+/// Turns mouse and keyboard input into a [Camera], in orbit or fly mode.
+///
+/// Input arrives already decoded, as pixels dragged, wheel steps and movement directions, so the
+/// controller does not depend on a windowing library.
+///
+/// ```
+/// use rigid_body_physics_engine::broadphase::Aabb;
+/// use rigid_body_physics_engine::camera::{CameraController, CameraMode};
+/// use rigid_body_physics_engine::math::Vec3;
+/// let mut controller = CameraController::default();
+/// controller.frame(&Aabb::new(Vec3::splat(4.0), Vec3::splat(6.0)));
+/// // A scene without bodies has nothing to frame.
+/// controller.frame(&Aabb::EMPTY);
+/// let camera = controller.camera();
+/// assert!((camera.forward() - (Vec3::splat(5.0) - camera.position).normalize()).length() < 1e-5);
+/// // Flying forward moves towards the framed box.
+/// controller.toggle_mode();
+/// assert_eq!(controller.mode, CameraMode::Fly);
+/// let before = controller.camera().position.distance(Vec3::splat(5.0));
+/// controller.fly(Vec3::new([0.0, 0.0, 1.0]), 0.1);
+/// assert!(controller.camera().position.distance(Vec3::splat(5.0)) < before);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraController {
+    pub mode: CameraMode,
+    /// Point the orbit camera looks at and circles around.
+    pub target: Vec3<f32>,
+    /// Distance of the orbit camera from the target.
+    pub distance: f32,
+    /// Heading around the world Y axis in radians, zero looks along -Z.
+    pub yaw: f32,
+    /// Angle below the horizon the camera looks down at, in radians.
+    pub pitch: f32,
+    /// Eye position in fly mode.
+    pub position: Vec3<f32>,
+    /// Fly mode speed in units per second.
+    pub speed: f32,
+    /// Radians turned per pixel dragged.
+    pub sensitivity: f32,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        let mut controller = Self {
+            mode: CameraMode::Orbit,
+            target: Vec3::new([0.0, 1.0, 0.0]),
+            distance: 15.0,
+            yaw: 0.0,
+            pitch: 0.4,
+            position: Vec3::ZERO,
+            speed: 5.0,
+            sensitivity: 0.005,
+            fov_y: 60f32.to_radians(),
+        };
+        controller.position = controller.orbit_eye();
+        controller
+    }
+}
+
+impl CameraController {
+    /// Unit vector the camera looks along.
+    pub fn forward(&self) -> Vec3<f32> {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new([-cos_pitch * sin_yaw, -sin_pitch, -cos_pitch * cos_yaw])
+    }
+
+    fn orbit_eye(&self) -> Vec3<f32> {
+        self.target - self.forward() * self.distance
+    }
+
+    /// The camera for the current state.
+    pub fn camera(&self) -> Camera {
+        let eye = match self.mode {
+            CameraMode::Orbit => self.orbit_eye(),
+            CameraMode::Fly => self.position,
+        };
+        let mut camera = Camera::look_at(eye, eye + self.forward(), Vec3::Y);
+        camera.fov_y = self.fov_y;
+        camera
+    }
+
+    /// Switches between orbit and fly mode while keeping the view where it is.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => {
+                self.position = self.orbit_eye();
+                CameraMode::Fly
+            }
+            CameraMode::Fly => {
+                self.target = self.position + self.forward() * self.distance;
+                CameraMode::Orbit
+            }
+        };
+    }
+
+    /// Turns the view by a mouse drag of `dx` and `dy` pixels. In orbit mode the camera moves
+    /// around the target, in fly mode it turns on the spot.
+    pub fn drag(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * self.sensitivity;
+        self.pitch = (self.pitch + dy * self.sensitivity).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+    }
+
+    /// Mouse wheel by `steps`, positive away from the user. Moves the orbit camera closer to
+    /// the target or speeds up flying.
+    pub fn zoom(&mut self, steps: f32) {
+        match self.mode {
+            CameraMode::Orbit => self.distance = (self.distance * ZOOM_STEP.powf(steps)).max(0.1),
+            CameraMode::Fly => self.speed /= ZOOM_STEP.powf(steps),
+        }
+    }
+
+    /// Moves for `dt` seconds along `direction`, given as right, up and forward components.
+    /// In orbit mode this pans the target.
+    pub fn fly(&mut self, direction: Vec3<f32>, dt: f32) {
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).try_normalize().unwrap_or(Vec3::X);
+        let offset = (right * direction.x() + Vec3::Y * direction.y() + forward * direction.z())
+            * (self.speed * dt);
+        match self.mode {
+            CameraMode::Orbit => self.target += offset,
+            CameraMode::Fly => self.position += offset,
+        }
+    }
+
+    /// Points the camera at the center of `aabb`, backing off far enough for all of it to be in
+    /// view. Empty boxes leave the camera where it is.
+    pub fn frame(&mut self, aabb: &Aabb) {
+        if aabb.is_empty() {
+            return;
+        }
+        let radius = (aabb.extents().length() * 0.5).max(0.1);
+        self.target = aabb.center();
+        self.distance = 1.1 * radius / (0.5 * self.fov_y).sin();
+        self.position = self.orbit_eye();
+    }
+}
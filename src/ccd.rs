@@ -0,0 +1,194 @@
+use crate::broadphase::Aabb;
+use crate::collider::Collider;
+use crate::math::{Quat, Transform, Vec3};
+use crate::narrowphase::{gjk, GjkResult, SupportMap, Transformed};
+
+const MAX_ITERATIONS: usize = 32;
+/// Fraction of the target distance that counts as having reached it.
+const TOLERANCE: f32 = 0.1;
+
+/// Motion of a body over one step: a constant translation and a rotation around the body origin
+/// at constant angular velocity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Motion {
+    pub start: Transform<f32>,
+    /// Displacement over the whole motion.
+    pub linear: Vec3<f32>,
+    /// Rotation over the whole motion as axis times angle.
+    pub angular: Vec3<f32>,
+}
+
+impl Motion {
+    /// Motion that moves from `start` to `end` along the shortest rotation.
+    pub fn between(start: Transform<f32>, end: Transform<f32>) -> Self {
+        let (axis, angle) = (end.rotation * start.rotation.conjugate()).to_axis_angle();
+        Self {
+            start,
+            linear: end.translation - start.translation,
+            angular: axis * angle,
+        }
+    }
+
+    /// Motion that stays at `transform`.
+    pub fn fixed(transform: Transform<f32>) -> Self {
+        Self {
+            start: transform,
+            linear: Vec3::ZERO,
+            angular: Vec3::ZERO,
+        }
+    }
+
+    /// Pose at fraction `t` of the motion.
+    pub fn at(&self, t: f32) -> Transform<f32> {
+        Transform::new(
+            self.start.translation + self.linear * t,
+            (Quat::from_scaled_axis(self.angular * t) * self.start.rotation).normalize(),
+        )
+    }
+
+    /// Box around everything within `radius` of the body origin during the motion.
+    pub fn swept_aabb(&self, radius: f32) -> Aabb {
+        let start = self.start.translation;
+        Aabb::from_points([start, start + self.linear]).fatten(radius)
+    }
+
+    /// Largest distance a point at most `radius` away from the body origin travels.
+    pub fn max_travel(&self, radius: f32) -> f32 {
+        self.linear.length() + self.angular.length() * radius
+    }
+}
+
+/// First fraction of the motions at which the two colliders come closer than `distance`, found by
+/// conservative advancement.
+///
+/// Shapes that start closer than `distance` are left to the discrete contacts unless they close
+/// in on each other to less than half their starting gap. Shapes that do not meet before the
+/// motions end, or that start out intersecting, give `None`. So do planes, which are solid half
+/// spaces that bodies can not tunnel through.
+///
+/// ```
+/// use std::sync::Arc;
+/// use rigid_body_physics_engine::ccd::{time_of_impact, Motion};
+/// use rigid_body_physics_engine::collider::Collider;
+/// use rigid_body_physics_engine::math::{Transform, Vec3};
+/// use rigid_body_physics_engine::stl::IndexedMesh;
+/// let cube = Collider::Convex(Arc::new(IndexedMesh::cuboid(Vec3::splat(0.5))));
+/// let wall = Motion::fixed(Transform::from_translation(Vec3::new([5.0, 0.0, 0.0])));
+/// // Moves straight through the wall within a single step.
+/// let bullet = Motion::between(
+///     Transform::IDENTITY,
+///     Transform::from_translation(Vec3::new([10.0, 0.0, 0.0])),
+/// );
+/// let t = time_of_impact(&cube, &bullet, &cube, &wall, 0.01).unwrap();
+/// assert!((bullet.at(t).translation.x() - 3.99).abs() < 1e-3);
+/// ```
+pub fn time_of_impact(
+    a: &Collider,
+    motion_a: &Motion,
+    b: &Collider,
+    motion_b: &Motion,
+    distance: f32,
+) -> Option<f32> {
+    let mut first: Option<f32> = None;
+    let mut keep_first = |t: Option<f32>| {
+        if let Some(t) = t.filter(|&t| first.is_none_or(|f| t < f)) {
+            first = Some(t);
+        }
+    };
+    match (a, b) {
+        (Collider::TriMesh(_), Collider::TriMesh(_)) => {}
+        (Collider::TriMesh(_), _) => keep_first(time_of_impact(b, motion_b, a, motion_a, distance)),
+        (_, Collider::TriMesh(mesh)) => {
+            let mesh_travel = motion_b.max_travel(b.bounding_radius());
+            for (shape_a, aabb_a) in a.convex_parts() {
+                let radius_a = radius(&aabb_a);
+                // Faces the part can reach, in the space of the mesh at the start of its motion.
+                let reach = motion_a
+                    .swept_aabb(radius_a)
+                    .fatten(mesh_travel + distance)
+                    .transformed(&motion_b.start.inverse());
+                mesh.query(&reach, |face| {
+                    let triangle = mesh.triangle(face);
+                    keep_first(convex_time_of_impact(
+                        (shape_a, radius_a),
+                        motion_a,
+                        (&triangle, radius(&Aabb::from_points(triangle.vertices))),
+                        motion_b,
+                        distance,
+                    ));
+                });
+            }
+        }
+        _ => {
+            for (shape_a, aabb_a) in a.convex_parts() {
+                for (shape_b, aabb_b) in b.convex_parts() {
+                    keep_first(convex_time_of_impact(
+                        (shape_a, radius(&aabb_a)),
+                        motion_a,
+                        (shape_b, radius(&aabb_b)),
+                        motion_b,
+                        distance,
+                    ));
+                }
+            }
+        }
+    }
+    first
+}
+
+/// Distance of the farthest corner of a body space box from the body origin.
+fn radius(aabb: &Aabb) -> f32 {
+    aabb.min.abs().max(aabb.max.abs()).length()
+}
+
+/// Time of impact of two convex shapes, each given with its bounding radius around the body
+/// origin.
+fn convex_time_of_impact(
+    (a, radius_a): (&(impl SupportMap + ?Sized), f32),
+    motion_a: &Motion,
+    (b, radius_b): (&(impl SupportMap + ?Sized), f32),
+    motion_b: &Motion,
+    distance: f32,
+) -> Option<f32> {
+    let spin = motion_a.angular.length() * radius_a + motion_b.angular.length() * radius_b;
+    let mut t = 0.0;
+    let tolerance = distance * TOLERANCE;
+    let mut target = distance;
+    for i in 0..MAX_ITERATIONS {
+        let pose_a = Transformed {
+            shape: a,
+            transform: motion_a.at(t),
+        };
+        let pose_b = Transformed {
+            shape: b,
+            transform: motion_b.at(t),
+        };
+        let (gap, normal) = match gjk(&pose_a, &pose_b) {
+            GjkResult::Separated {
+                distance: d,
+                point_a,
+                point_b,
+            } => (d, (point_b - point_a) / d),
+            GjkResult::Intersecting => return (i > 0).then_some(t),
+        };
+        if i == 0 && gap <= distance + tolerance {
+            // Already touching, which the discrete contacts handle as long as the shapes do not
+            // close in much further within this motion.
+            target = gap * 0.5;
+        }
+        if gap <= target + tolerance {
+            return (i > 0).then_some(t);
+        }
+        // Upper bound of how fast any point of A approaches B along the normal.
+        let closing = (motion_a.linear - motion_b.linear).dot(normal) + spin;
+        if closing <= 0.0 {
+            return None;
+        }
+        t += (gap - target) / closing;
+        if t > 1.0 {
+            return None;
+        }
+    }
+    // Still approaching, but every step so far was safe.
+    Some(t)
+}
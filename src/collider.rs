@@ -0,0 +1,97 @@
+use crate::broadphase::Aabb;
+use crate::math::{Transform, Vec3};
+use crate::narrowphase::SupportMap;
+use crate::shape::Shape;
+use crate::stl::IndexedMesh;
+use crate::trimesh::TriMeshCollider;
+use std::sync::Arc;
+
+/// Convex pieces that together form one non-convex collision shape.
+#[derive(Clone, Debug)]
+pub struct Compound {
+    /// Convex pieces in body space.
+    pub pieces: Vec<IndexedMesh>,
+    /// Body space bounding box of every piece, to skip pairs of pieces early.
+    pub aabbs: Vec<Aabb>,
+}
+
+impl Compound {
+    pub fn new(pieces: Vec<IndexedMesh>) -> Self {
+        let aabbs = pieces
+            .iter()
+            .map(|p| Aabb::from_points(p.vertices.iter().copied()))
+            .collect();
+        Self { pieces, aabbs }
+    }
+}
+
+/// Shape a body collides with.
+#[derive(Clone, Debug)]
+pub enum Collider {
+    /// The convex hull of a mesh, the mesh itself does not need to be convex.
+    Convex(Arc<IndexedMesh>),
+    /// A set of convex pieces, for example from
+    /// [convex_decomposition](IndexedMesh::convex_decomposition).
+    Compound(Arc<Compound>),
+    /// The exact triangles of a mesh, only for static bodies.
+    TriMesh(Arc<TriMeshCollider>),
+    /// A sphere, box, capsule, cylinder or plane.
+    Primitive(Shape),
+}
+
+impl Collider {
+    /// Convex parts of the shape together with their body space bounding boxes, none for
+    /// triangle meshes and planes.
+    pub fn convex_parts(&self) -> Vec<(&dyn SupportMap, Aabb)> {
+        match self {
+            Collider::Convex(mesh) => {
+                vec![(
+                    mesh.as_ref() as &dyn SupportMap,
+                    Aabb::from_points(mesh.vertices.iter().copied()),
+                )]
+            }
+            Collider::Compound(compound) => compound
+                .pieces
+                .iter()
+                .map(|p| p as &dyn SupportMap)
+                .zip(compound.aabbs.iter().copied())
+                .collect(),
+            Collider::TriMesh(_) | Collider::Primitive(Shape::Plane { .. }) => Vec::new(),
+            Collider::Primitive(shape) => vec![(shape as &dyn SupportMap, shape.aabb())],
+        }
+    }
+
+    /// Distance of the farthest point of the shape from the body origin.
+    pub fn bounding_radius(&self) -> f32 {
+        let radius =
+            |vertices: &[Vec3<f32>]| vertices.iter().map(|v| v.length()).fold(0.0, f32::max);
+        match self {
+            Collider::Convex(mesh) => radius(&mesh.vertices),
+            Collider::Compound(compound) => compound
+                .pieces
+                .iter()
+                .map(|p| radius(&p.vertices))
+                .fold(0.0, f32::max),
+            Collider::TriMesh(mesh) => radius(&mesh.mesh().vertices),
+            Collider::Primitive(shape) => shape.bounding_radius(),
+        }
+    }
+
+    /// World space bounding box under `transform`.
+    pub fn aabb(&self, transform: &Transform<f32>) -> Aabb {
+        match self {
+            Collider::Convex(mesh) => {
+                Aabb::from_points(mesh.vertices.iter().map(|&v| transform.transform_point(v)))
+            }
+            Collider::Compound(compound) => Aabb::from_points(
+                compound
+                    .pieces
+                    .iter()
+                    .flat_map(|p| p.vertices.iter())
+                    .map(|&v| transform.transform_point(v)),
+            ),
+            Collider::TriMesh(mesh) => mesh.aabb().transformed(transform),
+            Collider::Primitive(shape) => shape.aabb().transformed(transform),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use sdl2::keyboard::Keycode;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// Something the viewer does when a key is pressed or held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    CycleRenderMode,
+    ToggleFly,
+    SelectNext,
+    FrameSelected,
+    FrameAll,
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::Quit,
+        Action::ToggleHelp,
+        Action::CycleRenderMode,
+        Action::ToggleFly,
+        Action::SelectNext,
+        Action::FrameSelected,
+        Action::FrameAll,
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+    ];
+
+    /// Name used in bindings files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ToggleHelp => "help",
+            Action::CycleRenderMode => "render_mode",
+            Action::ToggleFly => "toggle_fly",
+            Action::SelectNext => "select_next",
+            Action::FrameSelected => "frame_selected",
+            Action::FrameAll => "frame_all",
+            Action::MoveForward => "forward",
+            Action::MoveBack => "back",
+            Action::MoveLeft => "left",
+            Action::MoveRight => "right",
+            Action::MoveUp => "up",
+            Action::MoveDown => "down",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Which key triggers each [Action].
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    keys: Vec<(Action, Keycode)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: vec![
+                (Action::Quit, Keycode::Escape),
+                (Action::ToggleHelp, Keycode::F1),
+                (Action::CycleRenderMode, Keycode::Tab),
+                (Action::ToggleFly, Keycode::V),
+                (Action::SelectNext, Keycode::N),
+                (Action::FrameSelected, Keycode::F),
+                (Action::FrameAll, Keycode::Home),
+                (Action::MoveForward, Keycode::W),
+                (Action::MoveBack, Keycode::S),
+                (Action::MoveLeft, Keycode::A),
+                (Action::MoveRight, Keycode::D),
+                (Action::MoveUp, Keycode::E),
+                (Action::MoveDown, Keycode::Q),
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Reads bindings from a text file with one `action = key` line per binding, for example
+    /// `frame_all = H`. Keys use SDL key names, lines starting with `#` are comments and actions
+    /// that are not mentioned keep their default key.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut bindings = Self::default();
+        let text = std::fs::read_to_string(path)?;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid =
+                |msg: String| Error::new(ErrorKind::InvalidData, format!("line {}: {msg}", i + 1));
+            let (action, key) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected `action = key`, found `{line}`")))?;
+            let action = Action::from_name(action.trim())
+                .ok_or_else(|| invalid(format!("unknown action `{}`", action.trim())))?;
+            let key = Keycode::from_name(key.trim())
+                .ok_or_else(|| invalid(format!("unknown key `{}`", key.trim())))?;
+            bindings.bind(action, key);
+        }
+        Ok(bindings)
+    }
+
+    /// Makes `key` trigger `action` instead of its previous key.
+    pub fn bind(&mut self, action: Action, key: Keycode) {
+        self.keys.retain(|&(a, _)| a != action);
+        self.keys.push((action, key));
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        self.keys.iter().find(|&&(_, k)| k == key).map(|&(a, _)| a)
+    }
+
+    /// One `key  action` line per binding, for showing them to the user.
+    pub fn help(&self) -> Vec<String> {
+        Action::ALL
+            .into_iter()
+            .filter_map(|a| Some(format!("{:<8} {}", self.key(a)?.name(), a.name())))
+            .collect()
+    }
+
+    /// The key bound to `action`.
+    pub fn key(&self, action: Action) -> Option<Keycode> {
+        self.keys
+            .iter()
+            .find(|&&(a, _)| a == action)
+            .map(|&(_, k)| k)
+    }
+}
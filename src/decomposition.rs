@@ -0,0 +1,253 @@
+use crate::math::Vec3;
+use crate::stl::{IndexedMesh, Vertex};
+use gxhash::{HashSet, HashSetExt};
+use std::io::Result;
+
+/// Settings of [IndexedMesh::convex_decomposition].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecompositionParams {
+    /// Number of voxels along the longest side of the mesh.
+    pub resolution: usize,
+    /// Largest accepted concavity of a piece, as the volume its hull adds on top of the piece
+    /// relative to the volume of the whole mesh.
+    pub concavity: f32,
+    /// Upper bound of the number of pieces.
+    pub max_pieces: usize,
+    /// Vertex budget for the hull of every piece, see [quickhull](crate::hull::quickhull).
+    pub max_hull_vertices: Option<usize>,
+}
+
+impl Default for DecompositionParams {
+    fn default() -> Self {
+        Self {
+            resolution: 32,
+            concavity: 0.01,
+            max_pieces: 16,
+            max_hull_vertices: Some(64),
+        }
+    }
+}
+
+type Voxel = [u32; 3];
+
+/// Solid voxel grid of a closed mesh.
+struct Grid {
+    origin: Vec3<f32>,
+    size: f32,
+}
+
+impl Grid {
+    fn corner(&self, c: Voxel) -> Vertex {
+        self.origin + Vec3::new(c.map(|v| v as f32)) * self.size
+    }
+}
+
+/// Marks every voxel whose center is inside the mesh, by casting one ray along X per row and
+/// filling the spans between crossings.
+fn voxelize(mesh: &IndexedMesh, resolution: usize) -> (Grid, Vec<Voxel>) {
+    let (min, max) = mesh.vertices.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), &v| (min.min(v), max.max(v)),
+    );
+    let size = (max - min).max_elem() / resolution.max(1) as f32;
+    let dims = ((max - min) / size).map(|e| e.ceil().max(1.0));
+    let grid = Grid { origin: min, size };
+
+    let mut voxels = Vec::new();
+    let mut crossings = Vec::new();
+    for k in 0..dims[2] as u32 {
+        for j in 0..dims[1] as u32 {
+            // Nudge the ray off the grid lines so it does not run exactly through mesh edges.
+            let y = min[1] + (j as f32 + 0.5 + 1.3e-4) * size;
+            let z = min[2] + (k as f32 + 0.5 + 0.7e-4) * size;
+            crossings.clear();
+            for face in &mesh.faces {
+                let [a, b, c] = face.vertices.map(|i| mesh.vertices[i]);
+                if let Some(x) = ray_x_crossing(a, b, c, y, z) {
+                    crossings.push(x);
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let first = ((span[0] - min[0]) / size - 0.5).ceil().max(0.0) as u32;
+                let last = ((span[1] - min[0]) / size - 0.5).floor().min(dims[0] - 1.0);
+                if last < 0.0 {
+                    continue;
+                }
+                for i in first..=last as u32 {
+                    voxels.push([i, j, k]);
+                }
+            }
+        }
+    }
+    (grid, voxels)
+}
+
+/// X coordinate where the line through `(y, z)` parallel to X crosses triangle `abc`.
+fn ray_x_crossing(a: Vertex, b: Vertex, c: Vertex, y: f32, z: f32) -> Option<f32> {
+    let edge = |p: Vertex, q: Vertex| (q[1] - p[1]) * (z - p[2]) - (q[2] - p[2]) * (y - p[1]);
+    let (wa, wb, wc) = (edge(b, c), edge(c, a), edge(a, b));
+    let inside = (wa >= 0.0 && wb >= 0.0 && wc >= 0.0) || (wa <= 0.0 && wb <= 0.0 && wc <= 0.0);
+    let sum = wa + wb + wc;
+    if !inside || sum == 0.0 {
+        return None;
+    }
+    Some((a[0] * wa + b[0] * wb + c[0] * wc) / sum)
+}
+
+struct Piece {
+    voxels: Vec<Voxel>,
+    hull: IndexedMesh,
+    concavity: f32,
+}
+
+/// Hull around the outer corners of a set of voxels.
+fn piece(
+    grid: &Grid,
+    voxels: Vec<Voxel>,
+    total_volume: f32,
+    max_vertices: Option<usize>,
+) -> Result<Piece> {
+    let set: HashSet<Voxel> = voxels.iter().copied().collect();
+    let mut corners: HashSet<Voxel> = HashSet::new();
+    for &[i, j, k] in &voxels {
+        let inner = i > 0
+            && j > 0
+            && k > 0
+            && [
+                [i - 1, j, k],
+                [i + 1, j, k],
+                [i, j - 1, k],
+                [i, j + 1, k],
+                [i, j, k - 1],
+                [i, j, k + 1],
+            ]
+            .iter()
+            .all(|n| set.contains(n));
+        if inner {
+            continue;
+        }
+        for c in 0..8u32 {
+            corners.insert([i + (c & 1), j + ((c >> 1) & 1), k + ((c >> 2) & 1)]);
+        }
+    }
+    let points: Vec<Vertex> = corners.into_iter().map(|c| grid.corner(c)).collect();
+    let hull = crate::hull::quickhull(&points, max_vertices)?;
+    let voxel_volume = voxels.len() as f32 * grid.size.powi(3);
+    let hull_volume = hull.mass_properties(1.0)?.volume;
+    Ok(Piece {
+        voxels,
+        hull,
+        concavity: (hull_volume - voxel_volume).max(0.0) / total_volume,
+    })
+}
+
+impl IndexedMesh {
+    /// Splits a closed mesh into convex pieces that approximate it, in the spirit of V-HACD.
+    ///
+    /// The mesh is voxelized, then the piece whose convex hull adds the most volume is cut by the
+    /// axis aligned plane that lowers that extra volume the most, until every piece is convex
+    /// enough or `max_pieces` is reached. The hulls are built around voxel corners, so they can
+    /// stick out of the mesh by up to one voxel.
+    ///
+    /// A hoop is split into pieces that leave its hole open, and the pieces are ordinary meshes
+    /// that can be exported for inspection:
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::broadphase::Aabb;
+    /// use rigid_body_physics_engine::decomposition::DecompositionParams;
+    /// use rigid_body_physics_engine::stl;
+    /// let mut file = std::fs::File::open("circle_hoop_1.stl").unwrap();
+    /// let hoop = stl::read_stl(&mut file).unwrap();
+    /// let pieces = hoop.convex_decomposition(&DecompositionParams::default()).unwrap();
+    /// assert!(pieces.len() > 1);
+    /// let center = Aabb::from_points(hoop.vertices.iter().copied()).center();
+    /// for piece in &pieces {
+    ///     let outside = piece.triangles().any(|t| {
+    ///         let [a, b, c] = t.vertices;
+    ///         (b - a).cross(c - a).dot(center - a) > 0.0
+    ///     });
+    ///     assert!(outside, "a piece fills the hole");
+    ///     let mut out = Vec::new();
+    ///     stl::write_stl(&mut out, piece.triangles()).unwrap();
+    /// }
+    /// ```
+    pub fn convex_decomposition(&self, params: &DecompositionParams) -> Result<Vec<IndexedMesh>> {
+        self.validate()?;
+        let (grid, voxels) = voxelize(self, params.resolution);
+        if voxels.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "mesh is thinner than one voxel, increase the resolution",
+            ));
+        }
+        let total_volume = voxels.len() as f32 * grid.size.powi(3);
+        let mut pieces = vec![piece(
+            &grid,
+            voxels,
+            total_volume,
+            params.max_hull_vertices,
+        )?];
+
+        while pieces.len() < params.max_pieces.max(1) {
+            let (worst, _) = pieces
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.voxels.len() > 1)
+                .max_by(|(_, a), (_, b)| a.concavity.total_cmp(&b.concavity))
+                .unwrap_or((0, &pieces[0]));
+            if pieces[worst].concavity <= params.concavity || pieces[worst].voxels.len() <= 1 {
+                break;
+            }
+            match best_split(&grid, &pieces[worst].voxels, total_volume, params)? {
+                Some((left, right)) => {
+                    pieces.swap_remove(worst);
+                    pieces.push(left);
+                    pieces.push(right);
+                }
+                // No plane can split it, keep the piece as it is.
+                None => pieces[worst].concavity = 0.0,
+            }
+        }
+        Ok(pieces.into_iter().map(|p| p.hull).collect())
+    }
+}
+
+/// Cuts `voxels` by the candidate plane that leaves the least concavity.
+fn best_split(
+    grid: &Grid,
+    voxels: &[Voxel],
+    total_volume: f32,
+    params: &DecompositionParams,
+) -> Result<Option<(Piece, Piece)>> {
+    const CANDIDATES_PER_AXIS: u32 = 8;
+    let (lo, hi) = voxels.iter().fold(([u32::MAX; 3], [0; 3]), |(lo, hi), v| {
+        (
+            [lo[0].min(v[0]), lo[1].min(v[1]), lo[2].min(v[2])],
+            [hi[0].max(v[0]), hi[1].max(v[1]), hi[2].max(v[2])],
+        )
+    });
+    let mut best: Option<(f32, Piece, Piece)> = None;
+    for axis in 0..3 {
+        let extent = hi[axis] - lo[axis];
+        if extent == 0 {
+            continue;
+        }
+        let stride = extent.div_ceil(CANDIDATES_PER_AXIS).max(1);
+        let mut cut = lo[axis] + stride.min(extent);
+        while cut <= hi[axis] {
+            let (left, right): (Vec<Voxel>, Vec<Voxel>) =
+                voxels.iter().partition(|v| v[axis] < cut);
+            if !left.is_empty() && !right.is_empty() {
+                let left = piece(grid, left, total_volume, params.max_hull_vertices)?;
+                let right = piece(grid, right, total_volume, params.max_hull_vertices)?;
+                let cost = left.concavity + right.concavity;
+                if best.as_ref().is_none_or(|b| cost < b.0) {
+                    best = Some((cost, left, right));
+                }
+            }
+            cut += stride;
+        }
+    }
+    Ok(best.map(|(_, left, right)| (left, right)))
+}
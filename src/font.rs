@@ -1,17 +1,137 @@
+use rigid_body_physics_engine::world::World;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
 
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+/// Blank columns and rows between glyphs, in font pixels.
+const SPACING: u32 = 1;
+/// Size of a font pixel on screen.
+const HUD_SCALE: u32 = 2;
+/// Gap between the HUD panels and the window border, in screen pixels.
+const MARGIN: i32 = 8;
+/// Weight of the newest frame in the smoothed HUD numbers.
+const SMOOTHING: f32 = 0.05;
 
-// Scale fonts to a reasonable size when they're too big (though they might look less smooth)
-fn get_centered_rect(rect_width: u32, rect_height: u32, cons_width: u32, cons_height: u32) -> Rect {
+const TEXT_COLOR: Color = Color::RGB(230, 230, 230);
+const PANEL_COLOR: Color = Color::RGBA(0, 0, 0, 160);
+
+/// Rows of the 5x7 bitmap of `c`, top to bottom, with the leftmost pixel in bit 4. Lowercase
+/// letters are drawn as uppercase and characters without a glyph as `?`.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Width and height of `lines` drawn with font pixels of `scale` screen pixels.
+pub fn text_size(lines: &[impl AsRef<str>], scale: u32) -> (u32, u32) {
+    let columns = lines
+        .iter()
+        .map(|l| l.as_ref().chars().count() as u32)
+        .max()
+        .unwrap_or(0);
+    let rows = lines.len() as u32;
+    let width = (columns * (GLYPH_WIDTH + SPACING)).saturating_sub(SPACING);
+    let height = (rows * (GLYPH_HEIGHT + SPACING)).saturating_sub(SPACING);
+    (width * scale, height * scale)
+}
+
+/// Draws `lines` with their top left corner at `x`, `y`, in font pixels of `scale` screen pixels.
+pub fn draw_text<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    lines: &[impl AsRef<str>],
+    x: i32,
+    y: i32,
+    scale: u32,
+    color: Color,
+) -> Result<(), String> {
+    let mut rects = Vec::new();
+    for (row, line) in lines.iter().enumerate() {
+        let top = y + (row as u32 * (GLYPH_HEIGHT + SPACING) * scale) as i32;
+        for (column, c) in line.as_ref().chars().enumerate() {
+            let left = x + (column as u32 * (GLYPH_WIDTH + SPACING) * scale) as i32;
+            for (dy, bits) in glyph(c).into_iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - dx)) != 0 {
+                        let px = left + (dx * scale) as i32;
+                        let py = top + (dy as u32 * scale) as i32;
+                        rects.push(Rect::new(px, py, scale, scale));
+                    }
+                }
+            }
+        }
+    }
+    canvas.set_draw_color(color);
+    canvas.fill_rects(&rects)
+}
+
+/// Centers a `rect_width` by `rect_height` rectangle on `screen`, scaled down to fit into
+/// `cons_width` by `cons_height` while keeping its aspect ratio.
+pub fn get_centered_rect(
+    rect_width: u32,
+    rect_height: u32,
+    cons_width: u32,
+    cons_height: u32,
+    screen: Rect,
+) -> Rect {
     let wr = rect_width as f32 / cons_width as f32;
     let hr = rect_height as f32 / cons_height as f32;
 
     let (w, h) = if wr > 1f32 || hr > 1f32 {
         if wr > hr {
-            println!("Scaling down! The text will look worse!");
             let h = (rect_height as f32 / wr) as i32;
             (cons_width as i32, h)
         } else {
-            println!("Scaling down! The text will look worse!");
             let w = (rect_width as f32 / hr) as i32;
             (w, cons_height as i32)
         }
@@ -19,7 +139,102 @@ fn get_centered_rect(rect_width: u32, rect_height: u32, cons_width: u32, cons_he
         (rect_width as i32, rect_height as i32)
     };
 
-    let cx = (SCREEN_WIDTH as i32 - w) / 2;
-    let cy = (SCREEN_HEIGHT as i32 - h) / 2;
-    rect!(cx, cy, w, h)
+    let cx = (screen.width() as i32 - w) / 2;
+    let cy = (screen.height() as i32 - h) / 2;
+    Rect::new(cx, cy, w.max(1) as u32, h.max(1) as u32)
+}
+
+/// On-screen statistics of the simulation and an optional centered help panel.
+pub struct Hud {
+    /// Usable area of the display the window fills.
+    screen: Rect,
+    /// Smoothed frames per second.
+    fps: f32,
+    /// Smoothed wall clock time of one simulation step in seconds.
+    step_time: f32,
+    /// Whether the help panel is shown.
+    pub show_help: bool,
+}
+
+impl Hud {
+    pub fn new(screen: Rect) -> Self {
+        Self {
+            screen,
+            fps: 0.0,
+            step_time: 0.0,
+            show_help: false,
+        }
+    }
+
+    /// Feeds the timings of a frame that took `frame_time` seconds and spent `step_time` seconds
+    /// in each of its `steps` simulation steps.
+    pub fn record_frame(&mut self, frame_time: f32, step_time: f32, steps: usize) {
+        if frame_time > 0.0 {
+            let fps = 1.0 / frame_time;
+            self.fps = if self.fps == 0.0 {
+                fps
+            } else {
+                self.fps + (fps - self.fps) * SMOOTHING
+            };
+        }
+        if steps > 0 {
+            self.step_time += (step_time - self.step_time) * SMOOTHING;
+        }
+    }
+
+    /// Draws the statistics in the top left corner and, if enabled, `help` in the middle.
+    pub fn draw<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        world: &World,
+        help: &[String],
+    ) -> Result<(), String> {
+        canvas.set_blend_mode(BlendMode::Blend);
+        let stats = [
+            format!("FPS      {:.1}", self.fps),
+            format!("STEP     {:.2} MS", self.step_time * 1000.0),
+            format!("BODIES   {}", world.bodies().len()),
+            format!("CONTACTS {}", world.contacts().len()),
+            format!("ENERGY   {:.1} J", world.total_energy()),
+        ];
+        let (width, height) = text_size(&stats, HUD_SCALE);
+        let pad = HUD_SCALE as i32 * 2;
+        canvas.set_draw_color(PANEL_COLOR);
+        canvas.fill_rect(Rect::new(
+            MARGIN,
+            MARGIN,
+            width + 2 * pad as u32,
+            height + 2 * pad as u32,
+        ))?;
+        draw_text(
+            canvas,
+            &stats,
+            MARGIN + pad,
+            MARGIN + pad,
+            HUD_SCALE,
+            TEXT_COLOR,
+        )?;
+
+        if self.show_help && !help.is_empty() {
+            // Lay the panel out at double the HUD size and shrink it if the window is too small.
+            let scale = 2 * HUD_SCALE;
+            let (width, height) = text_size(help, scale);
+            let cons_width = self.screen.width().saturating_sub(4 * MARGIN as u32);
+            let cons_height = self.screen.height().saturating_sub(4 * MARGIN as u32);
+            let rect = get_centered_rect(width, height, cons_width, cons_height, self.screen);
+            let scale = (scale * rect.height() / height).max(1);
+            let (width, height) = text_size(help, scale);
+            let x = rect.x() + (rect.width() as i32 - width as i32) / 2;
+            let y = rect.y() + (rect.height() as i32 - height as i32) / 2;
+            canvas.set_draw_color(PANEL_COLOR);
+            canvas.fill_rect(Rect::new(
+                x - MARGIN,
+                y - MARGIN,
+                width + 2 * MARGIN as u32,
+                height + 2 * MARGIN as u32,
+            ))?;
+            draw_text(canvas, help, x, y, scale, TEXT_COLOR)?;
+        }
+        Ok(())
+    }
 }
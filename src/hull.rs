@@ -0,0 +1,225 @@
+use crate::math::Vec3;
+use crate::stl::{IndexedMesh, IndexedTriangle, Vertex};
+use std::io::Result;
+
+struct Face {
+    vertices: [usize; 3],
+    normal: Vec3<f64>,
+    offset: f64,
+    /// Points in front of this face that are not on the hull yet.
+    outside: Vec<usize>,
+}
+
+impl Face {
+    fn new(vertices: [usize; 3], points: &[Vec3<f64>]) -> Self {
+        let [a, b, c] = vertices.map(|i| points[i]);
+        let normal = (b - a).cross(c - a).normalize();
+        Self {
+            vertices,
+            normal,
+            offset: normal.dot(a),
+            outside: Vec::new(),
+        }
+    }
+
+    fn distance(&self, p: Vec3<f64>) -> f64 {
+        self.normal.dot(p) - self.offset
+    }
+}
+
+/// Computes the convex hull of `points` with the Quickhull algorithm.
+///
+/// With `max_vertices` the hull is simplified by stopping once it has that many vertices. As the
+/// farthest point is always added first, the result is the best hull of that size the greedy
+/// strategy can find, and it always lies inside the exact hull.
+///
+/// The returned mesh is closed and has its faces pointing outwards.
+///
+/// ```
+/// use rigid_body_physics_engine::hull::quickhull;
+/// use rigid_body_physics_engine::stl::Vertex;
+/// let mut points: Vec<Vertex> = (0..8)
+///     .map(|i| Vertex::new([(i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32]))
+///     .collect();
+/// points.push(Vertex::new([0.5, 0.5, 0.5]));
+/// let hull = quickhull(&points, None).unwrap();
+/// assert_eq!(hull.vertices.len(), 8);
+/// assert_eq!(hull.faces.len(), 12);
+/// hull.validate().unwrap();
+/// ```
+pub fn quickhull(points: &[Vertex], max_vertices: Option<usize>) -> Result<IndexedMesh> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+    if max_vertices.is_some_and(|m| m < 4) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "a hull needs at least 4 vertices",
+        ));
+    }
+    let points: Vec<Vec3<f64>> = points.iter().map(|p| p.cast()).collect();
+    let points = points.as_slice();
+    if points.len() < 4 {
+        return Err(invalid("a hull needs at least 4 points"));
+    }
+
+    let scale = points
+        .iter()
+        .fold(Vec3::ZERO, |m: Vec3<f64>, p| m.max(p.abs()))
+        .dot(Vec3::splat(1.0));
+    let eps = 3.0 * f32::EPSILON as f64 * scale.max(f64::MIN_POSITIVE);
+
+    let [a, b, c, d] =
+        initial_simplex(points, eps).ok_or_else(|| invalid("points are coplanar"))?;
+    let mut faces: Vec<Face> = Vec::new();
+    for tri in [[a, b, c], [a, c, d], [a, d, b], [b, d, c]] {
+        faces.push(Face::new(tri, points));
+    }
+    // Orient the first face outwards, the others are wound consistently with it.
+    if faces[0].distance(points[d]) > 0.0 {
+        for f in &mut faces {
+            let [i, j, k] = f.vertices;
+            *f = Face::new([i, k, j], points);
+        }
+    }
+
+    let mut on_hull = vec![false; points.len()];
+    for i in [a, b, c, d] {
+        on_hull[i] = true;
+    }
+    let mut hull_vertices = 4;
+    assign_outside(
+        &mut faces,
+        (0..points.len()).filter(|&i| !on_hull[i]),
+        points,
+        eps,
+    );
+
+    while max_vertices.is_none_or(|m| hull_vertices < m) {
+        // Add the point that is farthest outside of all faces.
+        let farthest = faces
+            .iter()
+            .flat_map(|f| f.outside.iter().map(move |&i| (i, f.distance(points[i]))))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+        let Some((eye, _)) = farthest else {
+            break;
+        };
+        let p = points[eye];
+
+        let mut horizon: Vec<(usize, usize)> = Vec::new();
+        let mut orphans: Vec<usize> = Vec::new();
+        faces.retain_mut(|f| {
+            if f.distance(p) <= eps {
+                return true;
+            }
+            for e in 0..3 {
+                let edge = (f.vertices[e], f.vertices[(e + 1) % 3]);
+                if let Some(i) = horizon.iter().position(|&(u, v)| (v, u) == edge) {
+                    horizon.swap_remove(i);
+                } else {
+                    horizon.push(edge);
+                }
+            }
+            orphans.append(&mut f.outside);
+            false
+        });
+
+        let first_new = faces.len();
+        faces.extend(
+            horizon
+                .into_iter()
+                .map(|(u, v)| Face::new([u, v, eye], points)),
+        );
+        on_hull[eye] = true;
+        hull_vertices += 1;
+        orphans.retain(|&i| i != eye);
+        assign_outside(&mut faces[first_new..], orphans.into_iter(), points, eps);
+    }
+
+    // Compact the vertex list to the points that ended up on the hull.
+    let mut remap = vec![usize::MAX; points.len()];
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::with_capacity(faces.len());
+    for f in &faces {
+        let indices = f.vertices.map(|i| {
+            if remap[i] == usize::MAX {
+                remap[i] = vertices.len();
+                vertices.push(points[i].cast::<f32>());
+            }
+            remap[i]
+        });
+        triangles.push(IndexedTriangle {
+            normal: f.normal.cast(),
+            vertices: indices,
+        });
+    }
+    let hull = IndexedMesh {
+        vertices,
+        faces: triangles,
+    };
+    hull.validate()?;
+    Ok(hull)
+}
+
+/// Four points spanning a tetrahedron of non-zero volume, if there are any.
+fn initial_simplex(points: &[Vec3<f64>], eps: f64) -> Option<[usize; 4]> {
+    let extreme = |dir: Vec3<f64>| {
+        (0..points.len()).fold((0, 0), |(min, max), i| {
+            (
+                if points[i].dot(dir) < points[min].dot(dir) {
+                    i
+                } else {
+                    min
+                },
+                if points[i].dot(dir) > points[max].dot(dir) {
+                    i
+                } else {
+                    max
+                },
+            )
+        })
+    };
+    let (a, b) = [Vec3::X, Vec3::Y, Vec3::Z]
+        .into_iter()
+        .map(extreme)
+        .max_by(|x, y| {
+            let dx = points[x.0].distance(points[x.1]);
+            let dy = points[y.0].distance(points[y.1]);
+            dx.total_cmp(&dy)
+        })?;
+    let ab = points[b] - points[a];
+    if ab.length() <= eps {
+        return None;
+    }
+    let line_distance = |i: usize| ab.cross(points[i] - points[a]).length();
+    let c = (0..points.len()).max_by(|&x, &y| line_distance(x).total_cmp(&line_distance(y)))?;
+    if line_distance(c) <= eps * ab.length() {
+        return None;
+    }
+    let n = ab.cross(points[c] - points[a]).normalize();
+    let plane_distance = |i: usize| n.dot(points[i] - points[a]).abs();
+    let d = (0..points.len()).max_by(|&x, &y| plane_distance(x).total_cmp(&plane_distance(y)))?;
+    if plane_distance(d) <= eps {
+        return None;
+    }
+    Some([a, b, c, d])
+}
+
+/// Moves every point to the outside set of the first face it is in front of.
+fn assign_outside(
+    faces: &mut [Face],
+    candidates: impl Iterator<Item = usize>,
+    points: &[Vec3<f64>],
+    eps: f64,
+) {
+    for i in candidates {
+        if let Some(f) = faces.iter_mut().find(|f| f.distance(points[i]) > eps) {
+            f.outside.push(i);
+        }
+    }
+}
+
+impl IndexedMesh {
+    /// Convex hull of the mesh vertices, see [quickhull].
+    pub fn convex_hull(&self, max_vertices: Option<usize>) -> Result<IndexedMesh> {
+        quickhull(&self.vertices, max_vertices)
+    }
+}
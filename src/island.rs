@@ -0,0 +1,88 @@
+use crate::world::BodyHandle;
+
+/// When bodies are put to sleep.
+///
+/// Bodies that touch or are joined form an island, and an island only sleeps as a whole: once
+/// every body in it has been slower than the thresholds for `time_to_sleep` seconds.
+///
+/// ```
+/// use std::sync::Arc;
+/// use rigid_body_physics_engine::body::RigidBody;
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::stl::IndexedMesh;
+/// use rigid_body_physics_engine::world::World;
+/// let cuboid = |half_extents| Arc::new(IndexedMesh::cuboid(half_extents));
+/// let mut world = World::new();
+/// let mut floor = RigidBody::new_static(cuboid(Vec3::new([5.0, 0.5, 5.0])));
+/// floor.position = Vec3::new([0.0, -0.5, 0.0]);
+/// world.add_body(floor);
+/// let mut cube = RigidBody::new(cuboid(Vec3::splat(0.5)), 1.0).unwrap();
+/// cube.position = Vec3::new([0.0, 0.6, 0.0]);
+/// let cube = world.add_body(cube);
+/// for _ in 0..120 {
+///     world.step(world.timestep);
+/// }
+/// assert!(world.body(cube).is_sleeping());
+/// world.body_mut(cube).apply_force(Vec3::new([100.0, 0.0, 0.0]));
+/// world.step(world.timestep);
+/// assert!(!world.body(cube).is_sleeping());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SleepParams {
+    pub enabled: bool,
+    /// Linear speed below which a body counts as resting.
+    pub linear_threshold: f32,
+    /// Angular speed below which a body counts as resting, in radians per second.
+    pub angular_threshold: f32,
+    /// How long all bodies of an island have to rest before it sleeps.
+    pub time_to_sleep: f32,
+}
+
+impl Default for SleepParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            linear_threshold: 0.05,
+            angular_threshold: 0.05,
+            time_to_sleep: 0.5,
+        }
+    }
+}
+
+/// Groups of bodies connected through `edges`, found with a union-find.
+///
+/// Bodies for which `links` returns false, such as static bodies, do not connect their
+/// neighbours and are not part of any island.
+pub(crate) fn islands(
+    body_count: usize,
+    edges: impl Iterator<Item = (BodyHandle, BodyHandle)>,
+    links: impl Fn(usize) -> bool,
+) -> Vec<Vec<BodyHandle>> {
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut parent: Vec<usize> = (0..body_count).collect();
+    for (a, b) in edges {
+        if links(a.0) && links(b.0) {
+            let (ra, rb) = (find(&mut parent, a.0), find(&mut parent, b.0));
+            parent[ra] = rb;
+        }
+    }
+
+    let mut index = vec![usize::MAX; body_count];
+    let mut islands: Vec<Vec<BodyHandle>> = Vec::new();
+    for i in (0..body_count).filter(|&i| links(i)) {
+        let root = find(&mut parent, i);
+        if index[root] == usize::MAX {
+            index[root] = islands.len();
+            islands.push(Vec::new());
+        }
+        islands[index[root]].push(BodyHandle(i));
+    }
+    islands
+}
@@ -0,0 +1,386 @@
+use crate::body::RigidBody;
+use crate::math::{Transform, Vec3};
+use crate::solver::SolverBody;
+use crate::world::BodyHandle;
+
+/// Index of a joint inside a [World](crate::world::World).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JointHandle(pub usize);
+
+/// Drives a hinge towards a target angular velocity with limited torque.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Motor {
+    /// Angular velocity of A relative to B around the hinge axis, in radians per second.
+    pub target_velocity: f32,
+    /// Largest torque the motor can apply.
+    pub max_torque: f32,
+}
+
+/// What a joint allows the two bodies to do.
+///
+/// Axes and angles are taken from the joint frames: hinges turn and sliders move along the X axis
+/// of the frame. Hinge angles and slider offsets are those of A relative to B, measured between
+/// the Y axes and the origins of the two frames, so for a body attached to the world they are
+/// simply those of the body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointKind {
+    /// Ball and socket, the anchors stay together and the bodies turn freely.
+    Ball,
+    /// Rotation around one axis, with optional `[lower, upper]` angle limits in radians.
+    Hinge {
+        limits: Option<[f32; 2]>,
+        motor: Option<Motor>,
+    },
+    /// Translation along one axis without rotation, with optional `[lower, upper]` limits.
+    Slider { limits: Option<[f32; 2]> },
+    /// Welds the bodies together.
+    Fixed,
+    /// Keeps the distance of the anchors between `min` and `max`. With `min == max` it is a rigid
+    /// rod, with `min == 0` a rope.
+    Distance { min: f32, max: f32 },
+}
+
+/// Constraint between two bodies, or between a body and the world.
+///
+/// ```
+/// use std::sync::Arc;
+/// use rigid_body_physics_engine::body::RigidBody;
+/// use rigid_body_physics_engine::joint::JointKind;
+/// use rigid_body_physics_engine::math::{Transform, Vec3};
+/// use rigid_body_physics_engine::stl::IndexedMesh;
+/// use rigid_body_physics_engine::world::World;
+/// let bob = Arc::new(IndexedMesh::cuboid(Vec3::splat(0.1)));
+/// let mut world = World::new();
+/// let mut body = RigidBody::new(bob, 1000.0).unwrap();
+/// body.position = Vec3::new([1.0, 0.0, 0.0]);
+/// let bob = world.add_body(body);
+/// // A pendulum of length 1 swinging around Z.
+/// let pivot = Transform::from_translation(Vec3::ZERO);
+/// let rod = world.add_joint(JointKind::Ball, bob, None, pivot);
+/// for _ in 0..30 {
+///     world.step(world.timestep);
+/// }
+/// assert!((world.body(bob).position.length() - 1.0).abs() < 0.02);
+/// // The rod pulls the bob towards the pivot.
+/// let pull = world.joint(rod).impulse();
+/// assert!(pull.dot(world.body(bob).position) > 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub body_a: BodyHandle,
+    /// Second body, `None` attaches A to the world.
+    pub body_b: Option<BodyHandle>,
+    /// Joint frame in the body space of A.
+    pub frame_a: Transform<f32>,
+    /// Joint frame in the body space of B, or in world space without B.
+    pub frame_b: Transform<f32>,
+    pub kind: JointKind,
+    /// Whether the two bodies still collide with each other.
+    pub collide_connected: bool,
+    /// Accumulated impulse of every constraint row in the last step, for warm starting.
+    row_impulses: [f32; MAX_ROWS],
+    impulse: Vec3<f32>,
+    angular_impulse: Vec3<f32>,
+}
+
+const MAX_ROWS: usize = 8;
+
+impl Joint {
+    /// Creates a joint from frames given in the body spaces of A and B.
+    pub fn new(
+        kind: JointKind,
+        body_a: BodyHandle,
+        frame_a: Transform<f32>,
+        body_b: Option<BodyHandle>,
+        frame_b: Transform<f32>,
+    ) -> Self {
+        Self {
+            body_a,
+            body_b,
+            frame_a,
+            frame_b,
+            kind,
+            collide_connected: false,
+            row_impulses: [0.0; MAX_ROWS],
+            impulse: Vec3::ZERO,
+            angular_impulse: Vec3::ZERO,
+        }
+    }
+
+    /// Linear impulse the joint applied to B in the last step, A got the opposite. Divide by the
+    /// timestep for the reaction force.
+    pub fn impulse(&self) -> Vec3<f32> {
+        self.impulse
+    }
+
+    /// Angular impulse around the anchor the joint applied to B in the last step, A got the
+    /// opposite. Divide by the timestep for the reaction torque.
+    pub fn angular_impulse(&self) -> Vec3<f32> {
+        self.angular_impulse
+    }
+
+    /// Builds the constraint rows for the current positions of the bodies.
+    ///
+    /// Body index `world` stands for the static world when there is no B.
+    pub(crate) fn prepare(
+        &self,
+        bodies: &[RigidBody],
+        solver_bodies: &[SolverBody],
+        world: usize,
+        baumgarte: f32,
+        dt: f32,
+    ) -> JointConstraint {
+        let a = self.body_a.0;
+        let (b, transform_b, center_b) = match self.body_b {
+            Some(h) => (h.0, bodies[h.0].transform(), bodies[h.0].position),
+            None => (world, Transform::IDENTITY, Vec3::ZERO),
+        };
+        let frame_a = bodies[a].transform() * self.frame_a;
+        let frame_b = transform_b * self.frame_b;
+        let (pa, pb) = (frame_a.translation, frame_b.translation);
+        let ra = pa - bodies[a].position;
+        let rb = pb - center_b;
+        let d = pb - pa;
+        let axes_a = [Vec3::X, Vec3::Y, Vec3::Z].map(|e| frame_a.rotation.rotate(e));
+        let axes_b = [Vec3::X, Vec3::Y, Vec3::Z].map(|e| frame_b.rotation.rotate(e));
+
+        let mut c = JointConstraint {
+            a,
+            b,
+            rb,
+            rows: Vec::with_capacity(MAX_ROWS),
+        };
+        let mut builder = RowBuilder {
+            sa: &solver_bodies[a],
+            sb: &solver_bodies[b],
+            rows: &mut c.rows,
+            impulses: &self.row_impulses,
+            baumgarte,
+            dt,
+        };
+        let point_rows = |builder: &mut RowBuilder| {
+            for (slot, e) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().enumerate() {
+                builder.point(slot, e, ra, rb, d.dot(e), Bound::Equal);
+            }
+        };
+        let fixed_rotation_rows = |builder: &mut RowBuilder, first_slot: usize| {
+            let mut q = frame_b.rotation * frame_a.rotation.conjugate();
+            if q.w < 0.0 {
+                q = -q;
+            }
+            let error = q.vector() * 2.0;
+            for (i, e) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().enumerate() {
+                builder.angular(first_slot + i, e, error.dot(e), Bound::Equal);
+            }
+        };
+
+        match self.kind {
+            JointKind::Ball => point_rows(&mut builder),
+            JointKind::Fixed => {
+                point_rows(&mut builder);
+                fixed_rotation_rows(&mut builder, 3);
+            }
+            JointKind::Hinge { limits, motor } => {
+                point_rows(&mut builder);
+                let axis = axes_a[0];
+                let misalignment = axis.cross(axes_b[0]);
+                builder.angular(3, axes_a[1], misalignment.dot(axes_a[1]), Bound::Equal);
+                builder.angular(4, axes_a[2], misalignment.dot(axes_a[2]), Bound::Equal);
+                if let Some([lower, upper]) = limits {
+                    let angle = axes_b[1]
+                        .cross(axes_a[1])
+                        .dot(axis)
+                        .atan2(axes_a[1].dot(axes_b[1]));
+                    builder.angular(5, -axis, angle - lower, Bound::Positive);
+                    builder.angular(6, axis, upper - angle, Bound::Positive);
+                }
+                if let Some(motor) = motor {
+                    builder.motor(7, -axis, motor.target_velocity, motor.max_torque * dt);
+                }
+            }
+            JointKind::Slider { limits } => {
+                fixed_rotation_rows(&mut builder, 0);
+                // The slider axis is attached to A, so A turns around the anchor of B.
+                let ra = pb - bodies[a].position;
+                builder.point(3, axes_a[1], ra, rb, d.dot(axes_a[1]), Bound::Equal);
+                builder.point(4, axes_a[2], ra, rb, d.dot(axes_a[2]), Bound::Equal);
+                if let Some([lower, upper]) = limits {
+                    let offset = -d.dot(axes_a[0]);
+                    builder.point(5, -axes_a[0], ra, rb, offset - lower, Bound::Positive);
+                    builder.point(6, axes_a[0], ra, rb, upper - offset, Bound::Positive);
+                }
+            }
+            JointKind::Distance { min, max } => {
+                let length = d.length();
+                let n = d.try_normalize().unwrap_or(axes_a[0]);
+                if min == max {
+                    builder.point(0, n, ra, rb, length - min, Bound::Equal);
+                } else {
+                    if min > 0.0 {
+                        builder.point(0, n, ra, rb, length - min, Bound::Positive);
+                    }
+                    builder.point(1, -n, ra, rb, max - length, Bound::Positive);
+                }
+            }
+        }
+        c
+    }
+
+    /// Keeps the impulses of this step for warm starting and reporting.
+    pub(crate) fn store(&mut self, constraint: &JointConstraint) {
+        self.row_impulses = [0.0; MAX_ROWS];
+        self.impulse = Vec3::ZERO;
+        self.angular_impulse = Vec3::ZERO;
+        for row in &constraint.rows {
+            self.row_impulses[row.slot] = row.impulse;
+            self.impulse += row.linear * row.impulse;
+            self.angular_impulse += (row.angular_b - constraint.rb.cross(row.linear)) * row.impulse;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Bound {
+    /// `C = 0`
+    Equal,
+    /// `C >= 0`
+    Positive,
+}
+
+/// One scalar constraint `C(x) = 0` or `C(x) >= 0` with velocity
+/// `Cdot = linear . (vB - vA) + angular_b . wB - angular_a . wA`.
+pub(crate) struct Row {
+    slot: usize,
+    linear: Vec3<f32>,
+    angular_a: Vec3<f32>,
+    angular_b: Vec3<f32>,
+    mass: f32,
+    /// Velocity `Cdot` the row drives towards.
+    target: f32,
+    min_impulse: f32,
+    max_impulse: f32,
+    impulse: f32,
+}
+
+struct RowBuilder<'a> {
+    sa: &'a SolverBody,
+    sb: &'a SolverBody,
+    rows: &'a mut Vec<Row>,
+    impulses: &'a [f32; MAX_ROWS],
+    baumgarte: f32,
+    dt: f32,
+}
+
+impl RowBuilder<'_> {
+    /// Keeps the points at offsets `ra` and `rb` from moving apart along `dir`.
+    fn point(
+        &mut self,
+        slot: usize,
+        dir: Vec3<f32>,
+        ra: Vec3<f32>,
+        rb: Vec3<f32>,
+        error: f32,
+        bound: Bound,
+    ) {
+        self.push(slot, dir, ra.cross(dir), rb.cross(dir), error, bound);
+    }
+
+    /// Keeps the bodies from turning relative to each other around `axis`.
+    fn angular(&mut self, slot: usize, axis: Vec3<f32>, error: f32, bound: Bound) {
+        self.push(slot, Vec3::ZERO, axis, axis, error, bound);
+    }
+
+    fn motor(&mut self, slot: usize, axis: Vec3<f32>, velocity: f32, max_impulse: f32) {
+        let len = self.rows.len();
+        self.push(slot, Vec3::ZERO, axis, axis, 0.0, Bound::Equal);
+        // Nothing to drive when neither body can turn, for example while they sleep.
+        if self.rows.len() == len {
+            return;
+        }
+        let row = &mut self.rows[len];
+        row.target = velocity;
+        row.min_impulse = -max_impulse;
+        row.max_impulse = max_impulse;
+        row.impulse = row.impulse.clamp(-max_impulse, max_impulse);
+    }
+
+    fn push(
+        &mut self,
+        slot: usize,
+        linear: Vec3<f32>,
+        angular_a: Vec3<f32>,
+        angular_b: Vec3<f32>,
+        error: f32,
+        bound: Bound,
+    ) {
+        let k = (self.sa.inv_mass + self.sb.inv_mass) * linear.length_squared()
+            + angular_a.dot(self.sa.inv_inertia * angular_a)
+            + angular_b.dot(self.sb.inv_inertia * angular_b);
+        if k <= 0.0 {
+            return;
+        }
+        let (target, min_impulse) = match bound {
+            Bound::Equal => (-self.baumgarte / self.dt * error, f32::NEG_INFINITY),
+            // Speculative, like contacts: a positive gap may close within this step.
+            Bound::Positive if error > 0.0 => (-error / self.dt, 0.0),
+            Bound::Positive => (-self.baumgarte / self.dt * error, 0.0),
+        };
+        self.rows.push(Row {
+            slot,
+            linear,
+            angular_a,
+            angular_b,
+            mass: 1.0 / k,
+            target,
+            min_impulse,
+            max_impulse: f32::INFINITY,
+            impulse: self.impulses[slot].max(min_impulse),
+        });
+    }
+}
+
+/// Constraint rows of one joint for the current step.
+pub(crate) struct JointConstraint {
+    a: usize,
+    b: usize,
+    /// Anchor of B relative to its center of mass.
+    rb: Vec3<f32>,
+    rows: Vec<Row>,
+}
+
+impl JointConstraint {
+    fn apply(&self, bodies: &mut [SolverBody], row: &Row, impulse: f32) {
+        let a = &mut bodies[self.a];
+        a.linear -= row.linear * (impulse * a.inv_mass);
+        a.angular -= a.inv_inertia * row.angular_a * impulse;
+        let b = &mut bodies[self.b];
+        b.linear += row.linear * (impulse * b.inv_mass);
+        b.angular += b.inv_inertia * row.angular_b * impulse;
+    }
+
+    pub(crate) fn warm_start(&self, bodies: &mut [SolverBody]) {
+        for row in &self.rows {
+            self.apply(bodies, row, row.impulse);
+        }
+    }
+
+    pub(crate) fn clear_impulses(&mut self) {
+        for row in &mut self.rows {
+            row.impulse = 0.0;
+        }
+    }
+
+    pub(crate) fn solve(&mut self, bodies: &mut [SolverBody]) {
+        for i in 0..self.rows.len() {
+            let row = &self.rows[i];
+            let (a, b) = (&bodies[self.a], &bodies[self.b]);
+            let cdot = row.linear.dot(b.linear - a.linear) + row.angular_b.dot(b.angular)
+                - row.angular_a.dot(a.angular);
+            let old = row.impulse;
+            let impulse =
+                (old + row.mass * (row.target - cdot)).clamp(row.min_impulse, row.max_impulse);
+            self.rows[i].impulse = impulse;
+            self.apply(bodies, &self.rows[i], impulse - old);
+        }
+    }
+}
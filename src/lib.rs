@@ -0,0 +1,21 @@
+pub mod body;
+pub mod broadphase;
+pub mod camera;
+pub mod ccd;
+pub mod collider;
+pub mod decomposition;
+pub mod hull;
+pub mod integrator;
+pub mod island;
+pub mod joint;
+pub mod mass;
+pub mod material;
+pub mod math;
+pub mod narrowphase;
+pub mod render;
+pub mod scene;
+pub mod shape;
+pub mod solver;
+pub mod stl;
+pub mod trimesh;
+pub mod world;
@@ -1,12 +1,91 @@
 extern crate sdl2;
 
+mod controls;
+mod font;
+
+use controls::{Action, KeyBindings};
+use font::Hud;
+use rigid_body_physics_engine::broadphase::Aabb;
+use rigid_body_physics_engine::camera::CameraController;
+use rigid_body_physics_engine::math::Vec3;
+use rigid_body_physics_engine::render::{body_color, wireframe, Framebuffer, Light, Shading};
+use rigid_body_physics_engine::scene;
+use rigid_body_physics_engine::world::World;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
-use std::time::Duration;
-mod stl;
+use sdl2::keyboard::Scancode;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Point;
+use std::time::{Duration, Instant};
+
+/// How bodies are drawn, cycled with [Action::CycleRenderMode].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RenderMode {
+    Wireframe,
+    Flat,
+    Smooth,
+}
+
+impl RenderMode {
+    fn next(self) -> Self {
+        match self {
+            RenderMode::Wireframe => RenderMode::Flat,
+            RenderMode::Flat => RenderMode::Smooth,
+            RenderMode::Smooth => RenderMode::Wireframe,
+        }
+    }
+}
+
+const BACKGROUND: [u8; 3] = [16, 16, 24];
+
+/// Bounds of the dynamic bodies, or of everything if nothing moves. Empty without bodies.
+fn scene_bounds(world: &World) -> Aabb {
+    let bounds = |dynamic: bool| {
+        world
+            .iter()
+            .filter(|(_, body)| !dynamic || !body.is_static())
+            .fold(Aabb::EMPTY, |aabb, (_, body)| aabb.union(&body.aabb()))
+    };
+    let dynamic = bounds(true);
+    if dynamic.is_empty() {
+        bounds(false)
+    } else {
+        dynamic
+    }
+}
+
+/// Value following the command line flag `name`.
+fn arg(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return Some(
+                args.next()
+                    .unwrap_or_else(|| panic!("{name} needs a value")),
+            );
+        }
+    }
+    None
+}
+
+/// Reads key bindings from the file given with `--bindings <path>`, or uses the defaults.
+fn key_bindings() -> KeyBindings {
+    match arg("--bindings") {
+        Some(path) => KeyBindings::load(&path)
+            .unwrap_or_else(|e| panic!("unable to load key bindings from {path}: {e}")),
+        None => KeyBindings::default(),
+    }
+}
+
+/// Builds the built in scene or loads the scene file given with `--scene <name>`, or builds the
+/// demo scene.
+fn load_scene() -> World {
+    let name = arg("--scene").unwrap_or_else(|| "demo".to_string());
+    scene::open(&name).unwrap_or_else(|e| panic!("unable to load scene {name}: {e}"))
+}
 
 pub fn main() {
+    let bindings = key_bindings();
+    let mut world = load_scene();
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
@@ -24,34 +103,114 @@ pub fn main() {
         .build()
         .unwrap();
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    // Everything is drawn on the CPU, so no GPU is needed, not even with the dummy video driver.
+    let mut canvas = window.into_canvas().software().build().unwrap();
+    let (width, height) = canvas.output_size().unwrap();
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+        .unwrap();
+    let mut frame = Framebuffer::new(width, height);
+    let light = Light::default();
+    let mut mode = RenderMode::Smooth;
 
-    canvas.set_draw_color(Color::RGB(0, 255, 255));
-    canvas.clear();
-    canvas.present();
     let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut i = 0;
+    let mut controller = CameraController::default();
+    controller.frame(&scene_bounds(&world));
+    let mut selected = 0;
+    let mut hud = Hud::new(display_size);
+    let help = bindings.help();
+    let mut last_frame = Instant::now();
     'running: loop {
-        i = (i + 1) % 255;
-        canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-        canvas.clear();
         for event in event_pump.poll_iter() {
             match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
+                Event::Quit { .. } => break 'running,
                 Event::KeyDown {
-                    keycode: Some(v), ..
-                } => match v {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } => match bindings.action(key) {
+                    Some(Action::Quit) => break 'running,
+                    Some(Action::ToggleHelp) => hud.show_help = !hud.show_help,
+                    Some(Action::CycleRenderMode) => mode = mode.next(),
+                    Some(Action::ToggleFly) => controller.toggle_mode(),
+                    Some(Action::SelectNext) if !world.bodies().is_empty() => {
+                        selected = (selected + 1) % world.bodies().len()
+                    }
+                    Some(Action::FrameSelected) => {
+                        if let Some(body) = world.bodies().get(selected) {
+                            controller.frame(&body.aabb())
+                        }
+                    }
+                    Some(Action::FrameAll) => controller.frame(&scene_bounds(&world)),
                     _ => {}
                 },
+                Event::MouseMotion {
+                    mousestate,
+                    xrel,
+                    yrel,
+                    ..
+                } if mousestate.left() => controller.drag(xrel as f32, yrel as f32),
+                Event::MouseWheel { precise_y, .. } => controller.zoom(precise_y),
                 _ => {}
             }
         }
-        // The rest of the game loop goes here...
+        let now = Instant::now();
+        let frame_time = (now - last_frame).as_secs_f32();
+        last_frame = now;
+
+        let keyboard = event_pump.keyboard_state();
+        let held = |action| {
+            bindings
+                .key(action)
+                .and_then(Scancode::from_keycode)
+                .is_some_and(|s| keyboard.is_scancode_pressed(s))
+        };
+        let axis = |positive, negative| held(positive) as i32 as f32 - held(negative) as i32 as f32;
+        let direction = Vec3::new([
+            axis(Action::MoveRight, Action::MoveLeft),
+            axis(Action::MoveUp, Action::MoveDown),
+            axis(Action::MoveForward, Action::MoveBack),
+        ]);
+        controller.fly(direction, frame_time);
+        let camera = controller.camera();
+
+        let step_start = Instant::now();
+        let steps = world.advance(frame_time);
+        let step_time = step_start.elapsed().as_secs_f32() / steps.max(1) as f32;
+        hud.record_frame(frame_time, step_time, steps);
 
+        match mode {
+            RenderMode::Wireframe => {
+                let [r, g, b] = BACKGROUND;
+                canvas.set_draw_color(Color::RGB(r, g, b));
+                canvas.clear();
+                for (handle, body) in world.iter() {
+                    let [r, g, b] = body_color(handle, body);
+                    canvas.set_draw_color(Color::RGB(r, g, b));
+                    let transform = world.interpolated_transform(handle);
+                    for [a, b] in wireframe(&body.mesh, &transform, &camera, width, height) {
+                        let a = Point::new(a[0] as i32, a[1] as i32);
+                        let b = Point::new(b[0] as i32, b[1] as i32);
+                        canvas.draw_line(a, b).unwrap();
+                    }
+                }
+            }
+            RenderMode::Flat | RenderMode::Smooth => {
+                let shading = if mode == RenderMode::Flat {
+                    Shading::Flat
+                } else {
+                    Shading::Gouraud
+                };
+                frame.clear(BACKGROUND);
+                frame.draw_world(&world, &camera, &light, shading);
+                texture
+                    .update(None, frame.pixels(), 3 * width as usize)
+                    .unwrap();
+                canvas.copy(&texture, None, None).unwrap();
+            }
+        }
+        hud.draw(&mut canvas, &world, &help).unwrap();
         canvas.present();
         std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
@@ -0,0 +1,159 @@
+use crate::math::{Mat3, Vec3};
+use crate::stl::IndexedMesh;
+use std::io::Result;
+
+/// Mass distribution of a solid of uniform density.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MassProperties {
+    /// Enclosed volume.
+    pub volume: f32,
+    /// Volume times density.
+    pub mass: f32,
+    /// Center of mass in mesh coordinates.
+    pub center_of_mass: Vec3<f32>,
+    /// Inertia tensor about the center of mass, in mesh axes.
+    pub inertia: Mat3<f32>,
+    /// Eigenvalues of the inertia tensor, in ascending order.
+    pub principal_moments: Vec3<f32>,
+    /// Rotation whose columns are the unit eigenvectors of the inertia tensor, column `i`
+    /// belongs to `principal_moments[i]`.
+    pub principal_axes: Mat3<f32>,
+}
+
+impl MassProperties {
+    /// Inverse of the inertia tensor, built from the principal decomposition so that it stays
+    /// symmetric.
+    pub fn inv_inertia(&self) -> Mat3<f32> {
+        let axes = self.principal_axes;
+        axes * Mat3::from_diagonal(self.principal_moments.map(|m| 1.0 / m)) * axes.transpose()
+    }
+}
+
+impl IndexedMesh {
+    /// Integrates volume, center of mass and inertia of the solid enclosed by the mesh.
+    ///
+    /// Every face spans a signed tetrahedron with the origin, so the mesh has to be closed and
+    /// consistently oriented with its normals pointing outwards.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::math::Vec3;
+    /// use rigid_body_physics_engine::stl::{IndexedMesh, IndexedTriangle, Vertex};
+    /// let face = |vertices| IndexedTriangle { normal: Vertex::default(), vertices };
+    /// let tetrahedron = IndexedMesh {
+    ///     vertices: vec![
+    ///         Vertex::new([0.0, 0.0, 0.0]),
+    ///         Vertex::new([1.0, 0.0, 0.0]),
+    ///         Vertex::new([0.0, 1.0, 0.0]),
+    ///         Vertex::new([0.0, 0.0, 1.0]),
+    ///     ],
+    ///     faces: vec![face([0, 2, 1]), face([0, 1, 3]), face([0, 3, 2]), face([1, 2, 3])],
+    /// };
+    /// let props = tetrahedron.mass_properties(6.0).unwrap();
+    /// assert!((props.volume - 1.0 / 6.0).abs() < 1e-6);
+    /// assert!((props.mass - 1.0).abs() < 1e-6);
+    /// assert_eq!(props.center_of_mass, Vec3::new([0.25, 0.25, 0.25]));
+    /// ```
+    pub fn mass_properties(&self, density: f32) -> Result<MassProperties> {
+        self.validate()?;
+
+        let mut volume = 0.0f64;
+        let mut first = Vec3::<f64>::ZERO;
+        let mut second = Mat3::<f64>::ZERO;
+        for face in &self.faces {
+            let [a, b, c] = face.vertices.map(|i| self.vertices[i].cast::<f64>());
+            let det = a.dot(b.cross(c));
+            let sum = a + b + c;
+            volume += det / 6.0;
+            first += sum * (det / 24.0);
+            second = second
+                + (Mat3::outer(a, a)
+                    + Mat3::outer(b, b)
+                    + Mat3::outer(c, c)
+                    + Mat3::outer(sum, sum))
+                    * (det / 120.0);
+        }
+
+        if volume.is_nan() || volume <= 0.0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "mesh encloses a volume of {}, are the faces pointing inwards?",
+                    volume
+                ),
+            ));
+        }
+
+        let com = first / volume;
+        // Move the second moment to the center of mass and turn it into an inertia tensor.
+        let covariance = second - Mat3::outer(com, com) * volume;
+        let density = density as f64;
+        let inertia = (Mat3::IDENTITY * covariance.trace() - covariance) * density;
+
+        let (moments, axes) = jacobi_eigen(inertia);
+        Ok(MassProperties {
+            volume: volume as f32,
+            mass: (volume * density) as f32,
+            center_of_mass: com.cast(),
+            inertia: inertia.cast(),
+            principal_moments: moments.cast(),
+            principal_axes: axes.cast(),
+        })
+    }
+
+    /// Moves all vertices by `offset`, e.g. the negated center of mass to re-center the mesh.
+    pub fn translate(&mut self, offset: Vec3<f32>) {
+        for v in &mut self.vertices {
+            *v += offset;
+        }
+    }
+}
+
+/// Eigen decomposition of a symmetric 3x3 matrix with cyclic Jacobi rotations.
+///
+/// Returns the eigenvalues in ascending order and a right handed rotation whose columns are the
+/// matching unit eigenvectors.
+fn jacobi_eigen(m: Mat3<f64>) -> (Vec3<f64>, Mat3<f64>) {
+    let mut m = m.0;
+    // Columns of `v` accumulate the rotations.
+    let mut v = Mat3::<f64>::IDENTITY.0;
+    for _ in 0..32 {
+        let off = m[0][1].abs() + m[0][2].abs() + m[1][2].abs();
+        let scale = m[0][0].abs() + m[1][1].abs() + m[2][2].abs();
+        if off <= f64::EPSILON * scale {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if m[p][q] == 0.0 {
+                continue;
+            }
+            let theta = (m[q][q] - m[p][p]) / (2.0 * m[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            for row in &mut m {
+                let (mp, mq) = (row[p], row[q]);
+                row[p] = c * mp - s * mq;
+                row[q] = s * mp + c * mq;
+            }
+            let (mp, mq) = (m[p], m[q]);
+            m[p] = std::array::from_fn(|k| c * mp[k] - s * mq[k]);
+            m[q] = std::array::from_fn(|k| s * mp[k] + c * mq[k]);
+            for row in &mut v {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+
+    let v = Mat3(v);
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| m[a][a].total_cmp(&m[b][b]));
+    let values = Vec3::new(order.map(|i| m[i][i]));
+    let [a, b, mut c] = order.map(|i| v.col(i));
+    // Keep the axes right handed so they can be used as a rotation.
+    if a.cross(b).dot(c) < 0.0 {
+        c = -c;
+    }
+    (values, Mat3::from_cols([a, b, c]))
+}
@@ -0,0 +1,77 @@
+/// How the coefficients of two touching bodies are merged into one.
+///
+/// When the two bodies use different rules the one listed last wins, so `Max` beats `Multiply`
+/// beats `Min` beats `Average`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineRule::Average => 0.5 * (a + b),
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+}
+
+/// Surface properties of a body.
+///
+/// ```
+/// use rigid_body_physics_engine::material::{CombineRule, Material};
+/// let ice = Material {
+///     friction: 0.02,
+///     friction_combine: CombineRule::Min,
+///     ..Material::default()
+/// };
+/// let rubber = Material {
+///     friction: 1.0,
+///     restitution: 0.8,
+///     ..Material::default()
+/// };
+/// assert_eq!(ice.combined_friction(&rubber), 0.02);
+/// assert_eq!(ice.combined_restitution(&rubber), 0.4);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Coulomb friction coefficient, the ratio of the largest friction force to the normal force.
+    pub friction: f32,
+    /// Ratio of separating to approaching speed after an impact, 0 for no bounce at all.
+    pub restitution: f32,
+    pub friction_combine: CombineRule,
+    pub restitution_combine: CombineRule,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            friction: 0.5,
+            restitution: 0.0,
+            friction_combine: CombineRule::Average,
+            restitution_combine: CombineRule::Average,
+        }
+    }
+}
+
+impl Material {
+    /// Friction coefficient of a contact between `self` and `other`.
+    pub fn combined_friction(&self, other: &Self) -> f32 {
+        self.friction_combine
+            .max(other.friction_combine)
+            .combine(self.friction, other.friction)
+    }
+
+    /// Restitution coefficient of a contact between `self` and `other`.
+    pub fn combined_restitution(&self, other: &Self) -> f32 {
+        self.restitution_combine
+            .max(other.restitution_combine)
+            .combine(self.restitution, other.restitution)
+    }
+}
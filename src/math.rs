@@ -0,0 +1,751 @@
+use std::fmt::Debug;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+/// Floating point scalar the math types are generic over, implemented for `f32` and `f64`.
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+    const HALF: Self;
+    const EPSILON: Self;
+    const INFINITY: Self;
+    const PI: Self;
+
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+    fn signum(self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl Float for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const TWO: Self = 2.0;
+            const HALF: Self = 0.5;
+            const EPSILON: Self = $t::EPSILON;
+            const INFINITY: Self = $t::INFINITY;
+            const PI: Self = std::$t::consts::PI;
+
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn sqrt(self) -> Self {
+                $t::sqrt(self)
+            }
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+            fn sin(self) -> Self {
+                $t::sin(self)
+            }
+            fn cos(self) -> Self {
+                $t::cos(self)
+            }
+            fn tan(self) -> Self {
+                $t::tan(self)
+            }
+            fn acos(self) -> Self {
+                $t::acos(self.clamp(-1.0, 1.0))
+            }
+            fn atan2(self, other: Self) -> Self {
+                $t::atan2(self, other)
+            }
+            fn min(self, other: Self) -> Self {
+                $t::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                $t::max(self, other)
+            }
+            fn is_finite(self) -> bool {
+                $t::is_finite(self)
+            }
+            fn signum(self) -> Self {
+                $t::signum(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+/// Tolerance used by the approximate `PartialEq` implementations.
+static DEFAULT_EPSILON: f64 = 1e-6;
+
+macro_rules! eq_e {
+    ($v1:expr, $v2:expr, $ep:expr) => {
+        ($v1 - $v2).abs() < $ep
+    };
+}
+
+/// Three component vector, used for points, directions and STL vertices alike.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Vec3<F>(pub(crate) [F; 3]);
+
+impl<F> Vec3<F> {
+    /// Constructor from array.
+    pub const fn new(v: [F; 3]) -> Self {
+        Self(v)
+    }
+}
+
+impl<F: Float> Vec3<F> {
+    pub const ZERO: Self = Self([F::ZERO; 3]);
+    pub const X: Self = Self([F::ONE, F::ZERO, F::ZERO]);
+    pub const Y: Self = Self([F::ZERO, F::ONE, F::ZERO]);
+    pub const Z: Self = Self([F::ZERO, F::ZERO, F::ONE]);
+
+    pub fn splat(v: F) -> Self {
+        Self([v; 3])
+    }
+    pub fn x(&self) -> F {
+        self.0[0]
+    }
+    pub fn y(&self) -> F {
+        self.0[1]
+    }
+    pub fn z(&self) -> F {
+        self.0[2]
+    }
+    pub fn dot(self, other: Self) -> F {
+        self.0[0] * other.0[0] + self.0[1] * other.0[1] + self.0[2] * other.0[2]
+    }
+    pub fn cross(self, other: Self) -> Self {
+        let [ax, ay, az] = self.0;
+        let [bx, by, bz] = other.0;
+        Self([ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx])
+    }
+    pub fn length_squared(self) -> F {
+        self.dot(self)
+    }
+    pub fn length(self) -> F {
+        self.length_squared().sqrt()
+    }
+    /// Unit vector in the same direction, the zero vector stays zero.
+    pub fn normalize(self) -> Self {
+        self.try_normalize().unwrap_or(Self::ZERO)
+    }
+    /// Unit vector in the same direction, or `None` if the length is (almost) zero.
+    pub fn try_normalize(self) -> Option<Self> {
+        let length = self.length();
+        if length > F::EPSILON && length.is_finite() {
+            Some(self / length)
+        } else {
+            None
+        }
+    }
+    /// Component-wise product.
+    pub fn mul_elem(self, other: Self) -> Self {
+        self.zip(other, |a, b| a * b)
+    }
+    pub fn min(self, other: Self) -> Self {
+        self.zip(other, F::min)
+    }
+    pub fn max(self, other: Self) -> Self {
+        self.zip(other, F::max)
+    }
+    pub fn abs(self) -> Self {
+        self.map(F::abs)
+    }
+    pub fn min_elem(self) -> F {
+        self.0[0].min(self.0[1]).min(self.0[2])
+    }
+    pub fn max_elem(self) -> F {
+        self.0[0].max(self.0[1]).max(self.0[2])
+    }
+    /// Index of the largest component.
+    pub fn max_axis(self) -> usize {
+        let [x, y, z] = self.0;
+        if x >= y && x >= z {
+            0
+        } else if y >= z {
+            1
+        } else {
+            2
+        }
+    }
+    pub fn distance(self, other: Self) -> F {
+        (self - other).length()
+    }
+    pub fn lerp(self, other: Self, t: F) -> Self {
+        self + (other - self) * t
+    }
+    /// Some unit vector perpendicular to this one, which has to be normalized.
+    pub fn any_orthonormal(self) -> Self {
+        let helper = if self.0[0].abs() < F::from_f64(0.57735) {
+            Self::X
+        } else {
+            Self::Y
+        };
+        self.cross(helper).normalize()
+    }
+    pub fn is_finite(self) -> bool {
+        self.0.iter().all(|v| v.is_finite())
+    }
+    pub fn map(self, f: impl Fn(F) -> F) -> Self {
+        Self(self.0.map(f))
+    }
+    fn zip(self, other: Self, f: impl Fn(F, F) -> F) -> Self {
+        Self([
+            f(self.0[0], other.0[0]),
+            f(self.0[1], other.0[1]),
+            f(self.0[2], other.0[2]),
+        ])
+    }
+    /// Converts to another precision.
+    pub fn cast<G: Float>(self) -> Vec3<G> {
+        Vec3(self.0.map(|v| G::from_f64(v.to_f64())))
+    }
+}
+
+impl<F> From<Vec3<F>> for [F; 3] {
+    fn from(v: Vec3<F>) -> Self {
+        v.0
+    }
+}
+
+impl<F> From<[F; 3]> for Vec3<F> {
+    fn from(v: [F; 3]) -> Self {
+        Self(v)
+    }
+}
+
+impl<F> Index<usize> for Vec3<F> {
+    type Output = F;
+    fn index(&self, i: usize) -> &Self::Output {
+        assert!(i < 3);
+        &self.0[i]
+    }
+}
+
+impl<F> IndexMut<usize> for Vec3<F> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        assert!(i < 3);
+        &mut self.0[i]
+    }
+}
+
+impl<F: Float> PartialEq for Vec3<F> {
+    fn eq(&self, other: &Self) -> bool {
+        let ep = F::from_f64(DEFAULT_EPSILON);
+        eq_e!(self[0], other[0], ep) && eq_e!(self[1], other[1], ep) && eq_e!(self[2], other[2], ep)
+    }
+}
+
+impl<F: Float> Add for Vec3<F> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        self.zip(other, |a, b| a + b)
+    }
+}
+
+impl<F: Float> Sub for Vec3<F> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self.zip(other, |a, b| a - b)
+    }
+}
+
+impl<F: Float> Mul<F> for Vec3<F> {
+    type Output = Self;
+    fn mul(self, s: F) -> Self {
+        self.map(|v| v * s)
+    }
+}
+
+impl<F: Float> Div<F> for Vec3<F> {
+    type Output = Self;
+    fn div(self, s: F) -> Self {
+        self.map(|v| v / s)
+    }
+}
+
+impl<F: Float> Neg for Vec3<F> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.map(|v| -v)
+    }
+}
+
+impl<F: Float> AddAssign for Vec3<F> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<F: Float> SubAssign for Vec3<F> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<F: Float> MulAssign<F> for Vec3<F> {
+    fn mul_assign(&mut self, s: F) {
+        *self = *self * s;
+    }
+}
+
+impl<F: Float> DivAssign<F> for Vec3<F> {
+    fn div_assign(&mut self, s: F) {
+        *self = *self / s;
+    }
+}
+
+macro_rules! impl_scalar_mul {
+    ($t:ident) => {
+        impl Mul<Vec3<$t>> for $t {
+            type Output = Vec3<$t>;
+            fn mul(self, v: Vec3<$t>) -> Vec3<$t> {
+                v * self
+            }
+        }
+    };
+}
+
+impl_scalar_mul!(f32);
+impl_scalar_mul!(f64);
+
+impl<F: Float> std::iter::Sum for Vec3<F> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |a, b| a + b)
+    }
+}
+
+/// Row-major 3x3 matrix.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Mat3<F>(pub [[F; 3]; 3]);
+
+impl<F: Float> Mat3<F> {
+    pub const ZERO: Self = Self([[F::ZERO; 3]; 3]);
+    pub const IDENTITY: Self = Self([
+        [F::ONE, F::ZERO, F::ZERO],
+        [F::ZERO, F::ONE, F::ZERO],
+        [F::ZERO, F::ZERO, F::ONE],
+    ]);
+
+    pub fn from_rows(rows: [Vec3<F>; 3]) -> Self {
+        Self(rows.map(|r| r.0))
+    }
+    pub fn from_cols(cols: [Vec3<F>; 3]) -> Self {
+        Self::from_rows(cols).transpose()
+    }
+    pub fn from_diagonal(d: Vec3<F>) -> Self {
+        let mut m = Self::ZERO;
+        for i in 0..3 {
+            m.0[i][i] = d[i];
+        }
+        m
+    }
+    /// The matrix `a * b^T`.
+    pub fn outer(a: Vec3<F>, b: Vec3<F>) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| a[i] * b[j])
+        }))
+    }
+    /// The matrix `m` with `m * v == a.cross(v)`.
+    pub fn skew(a: Vec3<F>) -> Self {
+        let [x, y, z] = a.0;
+        Self([[F::ZERO, -z, y], [z, F::ZERO, -x], [-y, x, F::ZERO]])
+    }
+    pub fn row(&self, i: usize) -> Vec3<F> {
+        Vec3(self.0[i])
+    }
+    pub fn col(&self, j: usize) -> Vec3<F> {
+        Vec3([self.0[0][j], self.0[1][j], self.0[2][j]])
+    }
+    pub fn diagonal(&self) -> Vec3<F> {
+        Vec3([self.0[0][0], self.0[1][1], self.0[2][2]])
+    }
+    pub fn trace(&self) -> F {
+        self.0[0][0] + self.0[1][1] + self.0[2][2]
+    }
+    pub fn transpose(&self) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[j][i])
+        }))
+    }
+    pub fn determinant(&self) -> F {
+        self.row(0).dot(self.row(1).cross(self.row(2)))
+    }
+    /// Inverse of the matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() <= F::EPSILON * F::EPSILON || !det.is_finite() {
+            return None;
+        }
+        let (r0, r1, r2) = (self.row(0), self.row(1), self.row(2));
+        // Columns of the inverse are the cross products of the rows.
+        Some(Self::from_cols([r1.cross(r2), r2.cross(r0), r0.cross(r1)]) * (F::ONE / det))
+    }
+    pub fn map(&self, f: impl Fn(F) -> F) -> Self {
+        Self(self.0.map(|r| r.map(&f)))
+    }
+    /// Converts to another precision.
+    pub fn cast<G: Float>(self) -> Mat3<G> {
+        Mat3(self.0.map(|r| r.map(|v| G::from_f64(v.to_f64()))))
+    }
+}
+
+impl<F: Float> Add for Mat3<F> {
+    type Output = Self;
+    fn add(self, o: Self) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[i][j] + o.0[i][j])
+        }))
+    }
+}
+
+impl<F: Float> Sub for Mat3<F> {
+    type Output = Self;
+    fn sub(self, o: Self) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[i][j] - o.0[i][j])
+        }))
+    }
+}
+
+impl<F: Float> Mul for Mat3<F> {
+    type Output = Self;
+    fn mul(self, o: Self) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.row(i).dot(o.col(j)))
+        }))
+    }
+}
+
+impl<F: Float> Mul<Vec3<F>> for Mat3<F> {
+    type Output = Vec3<F>;
+    fn mul(self, v: Vec3<F>) -> Vec3<F> {
+        Vec3([self.row(0).dot(v), self.row(1).dot(v), self.row(2).dot(v)])
+    }
+}
+
+impl<F: Float> Mul<F> for Mat3<F> {
+    type Output = Self;
+    fn mul(self, s: F) -> Self {
+        self.map(|v| v * s)
+    }
+}
+
+/// Rotation quaternion `w + xi + yj + zk`.
+///
+/// Equality is approximate and treats `q` and `-q` as the same rotation.
+///
+/// ```
+/// use rigid_body_physics_engine::math::Quat;
+/// let q = Quat::new(0.6f64, 0.0, 0.8, 0.0);
+/// assert_eq!(q, -q);
+/// assert_ne!(q, Quat::new(0.8, 0.0, 0.6, 0.0));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Quat<F> {
+    pub w: F,
+    pub x: F,
+    pub y: F,
+    pub z: F,
+}
+
+impl<F: Float> Default for Quat<F> {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl<F: Float> Quat<F> {
+    pub const IDENTITY: Self = Self {
+        w: F::ONE,
+        x: F::ZERO,
+        y: F::ZERO,
+        z: F::ZERO,
+    };
+
+    pub fn new(w: F, x: F, y: F, z: F) -> Self {
+        Self { w, x, y, z }
+    }
+    pub fn from_scalar_vector(w: F, v: Vec3<F>) -> Self {
+        Self::new(w, v[0], v[1], v[2])
+    }
+    /// Rotation by `angle` radians around `axis`, which does not need to be normalized.
+    pub fn from_axis_angle(axis: Vec3<F>, angle: F) -> Self {
+        let half = angle * F::HALF;
+        Self::from_scalar_vector(half.cos(), axis.normalize() * half.sin())
+    }
+    /// Rotation by the vector's length around its direction.
+    pub fn from_scaled_axis(v: Vec3<F>) -> Self {
+        let angle = v.length();
+        if angle <= F::EPSILON {
+            Self::IDENTITY
+        } else {
+            Self::from_axis_angle(v, angle)
+        }
+    }
+    /// Shortest rotation that turns unit vector `from` into unit vector `to`.
+    pub fn from_rotation_arc(from: Vec3<F>, to: Vec3<F>) -> Self {
+        let d = from.dot(to);
+        if d < F::from_f64(-0.999999) {
+            return Self::from_axis_angle(from.any_orthonormal(), F::PI);
+        }
+        Self::from_scalar_vector(F::ONE + d, from.cross(to)).normalize()
+    }
+    /// Quaternion of a rotation matrix.
+    pub fn from_mat3(m: &Mat3<F>) -> Self {
+        let m = m.0;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let quarter = F::from_f64(0.25);
+        let q = if trace > F::ZERO {
+            let s = (trace + F::ONE).sqrt() * F::TWO;
+            Self::new(
+                quarter * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (F::ONE + m[0][0] - m[1][1] - m[2][2]).sqrt() * F::TWO;
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                quarter * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (F::ONE + m[1][1] - m[0][0] - m[2][2]).sqrt() * F::TWO;
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                quarter * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (F::ONE + m[2][2] - m[0][0] - m[1][1]).sqrt() * F::TWO;
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                quarter * s,
+            )
+        };
+        q.normalize()
+    }
+    pub fn vector(&self) -> Vec3<F> {
+        Vec3([self.x, self.y, self.z])
+    }
+    pub fn dot(&self, o: &Self) -> F {
+        self.w * o.w + self.x * o.x + self.y * o.y + self.z * o.z
+    }
+    pub fn length(&self) -> F {
+        self.dot(self).sqrt()
+    }
+    pub fn normalize(&self) -> Self {
+        let l = self.length();
+        if l <= F::EPSILON {
+            return Self::IDENTITY;
+        }
+        Self::new(self.w / l, self.x / l, self.y / l, self.z / l)
+    }
+    /// Inverse of a unit quaternion.
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+    pub fn rotate(&self, v: Vec3<F>) -> Vec3<F> {
+        // v' = v + 2w(q x v) + 2 q x (q x v)
+        let q = self.vector();
+        let t = q.cross(v) * F::TWO;
+        v + t * self.w + q.cross(t)
+    }
+    pub fn inverse_rotate(&self, v: Vec3<F>) -> Vec3<F> {
+        self.conjugate().rotate(v)
+    }
+    pub fn to_mat3(&self) -> Mat3<F> {
+        Mat3::from_cols([
+            self.rotate(Vec3::X),
+            self.rotate(Vec3::Y),
+            self.rotate(Vec3::Z),
+        ])
+    }
+    /// Rotation angle and unit axis, the axis is X for the identity.
+    pub fn to_axis_angle(&self) -> (Vec3<F>, F) {
+        let q = if self.w < F::ZERO { -*self } else { *self };
+        let s = q.vector().length();
+        let angle = F::TWO * s.atan2(q.w);
+        (q.vector().try_normalize().unwrap_or(Vec3::X), angle)
+    }
+    /// Orientation after rotating with angular velocity `omega` for `dt`.
+    pub fn integrate(&self, omega: Vec3<F>, dt: F) -> Self {
+        let spin = Self::from_scalar_vector(F::ZERO, omega) * *self;
+        let h = dt * F::HALF;
+        Self::new(
+            self.w + spin.w * h,
+            self.x + spin.x * h,
+            self.y + spin.y * h,
+            self.z + spin.z * h,
+        )
+        .normalize()
+    }
+    /// Normalized linear interpolation along the shorter arc.
+    pub fn nlerp(&self, other: &Self, t: F) -> Self {
+        let o = if self.dot(other) < F::ZERO {
+            -*other
+        } else {
+            *other
+        };
+        Self::new(
+            self.w + (o.w - self.w) * t,
+            self.x + (o.x - self.x) * t,
+            self.y + (o.y - self.y) * t,
+            self.z + (o.z - self.z) * t,
+        )
+        .normalize()
+    }
+    /// Converts to another precision.
+    pub fn cast<G: Float>(self) -> Quat<G> {
+        let c = |v: F| G::from_f64(v.to_f64());
+        Quat::new(c(self.w), c(self.x), c(self.y), c(self.z))
+    }
+}
+
+impl<F: Float> Mul for Quat<F> {
+    type Output = Self;
+    fn mul(self, o: Self) -> Self {
+        let (a, b) = (self.vector(), o.vector());
+        Self::from_scalar_vector(self.w * o.w - a.dot(b), b * self.w + a * o.w + a.cross(b))
+    }
+}
+
+impl<F: Float> Mul<Vec3<F>> for Quat<F> {
+    type Output = Vec3<F>;
+    fn mul(self, v: Vec3<F>) -> Vec3<F> {
+        self.rotate(v)
+    }
+}
+
+impl<F: Float> Neg for Quat<F> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.w, -self.x, -self.y, -self.z)
+    }
+}
+
+impl<F: Float> PartialEq for Quat<F> {
+    fn eq(&self, other: &Self) -> bool {
+        let ep = F::from_f64(DEFAULT_EPSILON);
+        let close = |o: &Self| {
+            eq_e!(self.w, o.w, ep)
+                && eq_e!(self.x, o.x, ep)
+                && eq_e!(self.y, o.y, ep)
+                && eq_e!(self.z, o.z, ep)
+        };
+        close(other) || close(&-*other)
+    }
+}
+
+/// Rigid transformation, rotation followed by translation.
+///
+/// ```
+/// use rigid_body_physics_engine::math::{Quat, Transform, Vec3};
+/// let t = Transform::new(
+///     Vec3::new([1.0f64, 0.0, 0.0]),
+///     Quat::from_axis_angle(Vec3::Z, std::f64::consts::FRAC_PI_2),
+/// );
+/// let p = t.transform_point(Vec3::X);
+/// assert_eq!(p, Vec3::new([1.0, 1.0, 0.0]));
+/// assert_eq!(t.inverse_transform_point(p), Vec3::X);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Transform<F> {
+    pub translation: Vec3<F>,
+    pub rotation: Quat<F>,
+}
+
+impl<F: Float> PartialEq for Transform<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.translation == other.translation && self.rotation == other.rotation
+    }
+}
+
+impl<F: Float> Default for Transform<F> {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl<F: Float> Transform<F> {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+    };
+
+    pub fn new(translation: Vec3<F>, rotation: Quat<F>) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+    pub fn from_translation(translation: Vec3<F>) -> Self {
+        Self::new(translation, Quat::IDENTITY)
+    }
+    pub fn transform_point(&self, p: Vec3<F>) -> Vec3<F> {
+        self.rotation.rotate(p) + self.translation
+    }
+    pub fn transform_vector(&self, v: Vec3<F>) -> Vec3<F> {
+        self.rotation.rotate(v)
+    }
+    pub fn inverse_transform_point(&self, p: Vec3<F>) -> Vec3<F> {
+        self.rotation.inverse_rotate(p - self.translation)
+    }
+    pub fn inverse_transform_vector(&self, v: Vec3<F>) -> Vec3<F> {
+        self.rotation.inverse_rotate(v)
+    }
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.conjugate();
+        Self::new(-rotation.rotate(self.translation), rotation)
+    }
+    /// Interpolates translation linearly and rotation along the shorter arc.
+    pub fn lerp(&self, other: &Self, t: F) -> Self {
+        Self::new(
+            self.translation.lerp(other.translation, t),
+            self.rotation.nlerp(&other.rotation, t),
+        )
+    }
+}
+
+impl<F: Float> Mul for Transform<F> {
+    type Output = Self;
+    /// Applies `o` first, then `self`.
+    fn mul(self, o: Self) -> Self {
+        Self::new(
+            self.transform_point(o.translation),
+            (self.rotation * o.rotation).normalize(),
+        )
+    }
+}
@@ -0,0 +1,1283 @@
+use crate::body::RigidBody;
+use crate::collider::Collider;
+use crate::math::{Transform, Vec3};
+use crate::shape::Shape;
+use crate::stl::{IndexedMesh, Triangle};
+use crate::trimesh::TriMeshCollider;
+use crate::world::BodyHandle;
+
+/// Convex shape described by its support function.
+///
+/// Non-convex shapes behave like their convex hull.
+pub trait SupportMap {
+    /// Point of the shape that is furthest in direction `dir`.
+    fn support(&self, dir: Vec3<f32>) -> Vec3<f32>;
+
+    /// All points of the shape that are within `tolerance` of the support plane in direction
+    /// `dir`, that is the vertices of the feature facing `dir`. Used to build contact manifolds
+    /// with more than one point.
+    fn support_points(&self, dir: Vec3<f32>, tolerance: f32) -> Vec<Vec3<f32>> {
+        let _ = tolerance;
+        vec![self.support(dir)]
+    }
+}
+
+impl SupportMap for IndexedMesh {
+    fn support(&self, dir: Vec3<f32>) -> Vec3<f32> {
+        self.vertices
+            .iter()
+            .copied()
+            .max_by(|a, b| a.dot(dir).total_cmp(&b.dot(dir)))
+            .unwrap_or(Vec3::ZERO)
+    }
+
+    fn support_points(&self, dir: Vec3<f32>, tolerance: f32) -> Vec<Vec3<f32>> {
+        let max = self.support(dir).dot(dir);
+        self.vertices
+            .iter()
+            .copied()
+            .filter(|v| v.dot(dir) >= max - tolerance)
+            .collect()
+    }
+}
+
+impl SupportMap for Triangle {
+    fn support(&self, dir: Vec3<f32>) -> Vec3<f32> {
+        self.vertices
+            .into_iter()
+            .max_by(|a, b| a.dot(dir).total_cmp(&b.dot(dir)))
+            .unwrap_or(Vec3::ZERO)
+    }
+
+    fn support_points(&self, dir: Vec3<f32>, tolerance: f32) -> Vec<Vec3<f32>> {
+        let max = self.support(dir).dot(dir);
+        self.vertices
+            .into_iter()
+            .filter(|v| v.dot(dir) >= max - tolerance)
+            .collect()
+    }
+}
+
+/// A shape placed in the world by a transform.
+pub struct Transformed<'a, S: ?Sized> {
+    pub shape: &'a S,
+    pub transform: Transform<f32>,
+}
+
+impl<'a, S: SupportMap + ?Sized> SupportMap for Transformed<'a, S> {
+    fn support(&self, dir: Vec3<f32>) -> Vec3<f32> {
+        let local = self
+            .shape
+            .support(self.transform.inverse_transform_vector(dir));
+        self.transform.transform_point(local)
+    }
+
+    fn support_points(&self, dir: Vec3<f32>, tolerance: f32) -> Vec<Vec3<f32>> {
+        let local_dir = self.transform.inverse_transform_vector(dir);
+        let mut points = self.shape.support_points(local_dir, tolerance);
+        for p in &mut points {
+            *p = self.transform.transform_point(*p);
+        }
+        points
+    }
+}
+
+/// Point of the Minkowski difference `A - B` together with the points of A and B it came from.
+#[derive(Clone, Copy, Debug)]
+struct SupportPoint {
+    w: Vec3<f32>,
+    a: Vec3<f32>,
+    b: Vec3<f32>,
+}
+
+fn minkowski_support(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+    dir: Vec3<f32>,
+) -> SupportPoint {
+    let a = a.support(dir);
+    let b = b.support(-dir);
+    SupportPoint { w: a - b, a, b }
+}
+
+/// Outcome of [gjk].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GjkResult {
+    /// The shapes overlap or touch.
+    Intersecting,
+    /// The shapes are apart, `point_a` and `point_b` are the closest points.
+    Separated {
+        distance: f32,
+        point_a: Vec3<f32>,
+        point_b: Vec3<f32>,
+    },
+}
+
+const GJK_MAX_ITERATIONS: usize = 64;
+const GJK_TOLERANCE: f32 = 1e-6;
+
+/// Closest point of a simplex to the origin, as barycentric weights of its vertices.
+fn closest_on_simplex(simplex: &[SupportPoint]) -> Vec<f32> {
+    match simplex {
+        [_] => vec![1.0],
+        [a, b] => {
+            let t = segment_weight(a.w, b.w);
+            vec![1.0 - t, t]
+        }
+        [a, b, c] => triangle_weights(a.w, b.w, c.w).to_vec(),
+        [a, b, c, d] => tetrahedron_weights(a.w, b.w, c.w, d.w).to_vec(),
+        _ => unreachable!("simplex with {} vertices", simplex.len()),
+    }
+}
+
+/// Parameter of the point on segment `ab` closest to the origin.
+fn segment_weight(a: Vec3<f32>, b: Vec3<f32>) -> f32 {
+    let ab = b - a;
+    let len = ab.length_squared();
+    if len <= f32::EPSILON {
+        return 0.0;
+    }
+    (-a.dot(ab) / len).clamp(0.0, 1.0)
+}
+
+/// Barycentric weights of the point on triangle `abc` closest to the origin, after Ericson,
+/// Real-Time Collision Detection, 5.1.5.
+fn triangle_weights(a: Vec3<f32>, b: Vec3<f32>, c: Vec3<f32>) -> [f32; 3] {
+    let ab = b - a;
+    let ac = c - a;
+    let d1 = -ab.dot(a);
+    let d2 = -ac.dot(a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return [1.0, 0.0, 0.0];
+    }
+    let d3 = -ab.dot(b);
+    let d4 = -ac.dot(b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return [0.0, 1.0, 0.0];
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return [1.0 - v, v, 0.0];
+    }
+    let d5 = -ab.dot(c);
+    let d6 = -ac.dot(c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return [0.0, 0.0, 1.0];
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return [1.0 - w, 0.0, w];
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return [0.0, 1.0 - w, w];
+    }
+    let denom = va + vb + vc;
+    if denom.abs() <= f32::EPSILON {
+        // Degenerate triangle, fall back to its longest edge.
+        let t = segment_weight(b, c);
+        return [0.0, 1.0 - t, t];
+    }
+    let v = vb / denom;
+    let w = vc / denom;
+    [1.0 - v - w, v, w]
+}
+
+/// Barycentric weights of the point of tetrahedron `abcd` closest to the origin.
+fn tetrahedron_weights(a: Vec3<f32>, b: Vec3<f32>, c: Vec3<f32>, d: Vec3<f32>) -> [f32; 4] {
+    // Faces with the index of the vertex opposite to them.
+    let faces = [
+        ([0, 1, 2], 3),
+        ([0, 2, 3], 1),
+        ([0, 3, 1], 2),
+        ([1, 3, 2], 0),
+    ];
+    let p = [a, b, c, d];
+    let mut best: Option<(f32, [f32; 4])> = None;
+    for (face, opposite) in faces {
+        let [i, j, k] = face;
+        let n = (p[j] - p[i]).cross(p[k] - p[i]);
+        let origin_side = -p[i].dot(n);
+        let opposite_side = (p[opposite] - p[i]).dot(n);
+        // The origin is on the far side of this face, or the tetrahedron is flat.
+        if origin_side * opposite_side < 0.0 || opposite_side.abs() <= f32::EPSILON {
+            let t = triangle_weights(p[i], p[j], p[k]);
+            let closest = p[i] * t[0] + p[j] * t[1] + p[k] * t[2];
+            let distance = closest.length_squared();
+            if best.is_none_or(|(d, _)| distance < d) {
+                let mut weights = [0.0; 4];
+                weights[i] = t[0];
+                weights[j] = t[1];
+                weights[k] = t[2];
+                best = Some((distance, weights));
+            }
+        }
+    }
+    match best {
+        Some((_, weights)) => weights,
+        // The origin is inside.
+        None => [0.25; 4],
+    }
+}
+
+/// Runs GJK on the Minkowski difference and returns the final simplex together with the result.
+fn gjk_simplex(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+) -> (GjkResult, Vec<SupportPoint>) {
+    let mut simplex = vec![minkowski_support(a, b, Vec3::X)];
+    let mut weights = vec![1.0];
+    let mut v = simplex[0].w;
+
+    for _ in 0..GJK_MAX_ITERATIONS {
+        let vv = v.length_squared();
+        if vv <= GJK_TOLERANCE * GJK_TOLERANCE {
+            return (GjkResult::Intersecting, simplex);
+        }
+        let p = minkowski_support(a, b, -v);
+        // No support point gets closer to the origin than the current estimate.
+        let converged = vv - v.dot(p.w) <= GJK_TOLERANCE * vv.max(1.0);
+        let duplicate = simplex
+            .iter()
+            .any(|s| (s.w - p.w).length_squared() <= 1e-12);
+        if converged || duplicate {
+            break;
+        }
+        simplex.push(p);
+        weights = closest_on_simplex(&simplex);
+        if simplex.len() == 4 && weights.iter().all(|&w| w > 0.0) {
+            return (GjkResult::Intersecting, simplex);
+        }
+        let mut i = 0;
+        simplex.retain(|_| {
+            i += 1;
+            weights[i - 1] > 0.0
+        });
+        weights.retain(|&w| w > 0.0);
+        v = simplex.iter().zip(&weights).map(|(s, &w)| s.w * w).sum();
+    }
+
+    let point_a = simplex.iter().zip(&weights).map(|(s, &w)| s.a * w).sum();
+    let point_b: Vec3<f32> = simplex.iter().zip(&weights).map(|(s, &w)| s.b * w).sum();
+    let distance = v.length();
+    if distance <= GJK_TOLERANCE {
+        return (GjkResult::Intersecting, simplex);
+    }
+    (
+        GjkResult::Separated {
+            distance,
+            point_a,
+            point_b,
+        },
+        simplex,
+    )
+}
+
+/// Gilbert-Johnson-Keerthi distance query between two convex shapes.
+///
+/// ```
+/// use rigid_body_physics_engine::math::{Transform, Vec3};
+/// use rigid_body_physics_engine::narrowphase::{gjk, GjkResult, Transformed};
+/// use rigid_body_physics_engine::stl::{IndexedMesh, IndexedTriangle};
+/// let face = |vertices| IndexedTriangle { normal: Vec3::default(), vertices };
+/// let tetrahedron = IndexedMesh {
+///     vertices: vec![
+///         Vec3::new([0.0, 0.0, 0.0]),
+///         Vec3::new([1.0, 0.0, 0.0]),
+///         Vec3::new([0.0, 1.0, 0.0]),
+///         Vec3::new([0.0, 0.0, 1.0]),
+///     ],
+///     faces: vec![face([0, 2, 1]), face([0, 1, 3]), face([0, 3, 2]), face([1, 2, 3])],
+/// };
+/// let moved = Transformed {
+///     shape: &tetrahedron,
+///     transform: Transform::from_translation(Vec3::new([3.0, 0.0, 0.0])),
+/// };
+/// match gjk(&tetrahedron, &moved) {
+///     GjkResult::Separated { distance, .. } => assert!((distance - 2.0).abs() < 1e-5),
+///     GjkResult::Intersecting => unreachable!(),
+/// }
+/// ```
+pub fn gjk(a: &(impl SupportMap + ?Sized), b: &(impl SupportMap + ?Sized)) -> GjkResult {
+    gjk_simplex(a, b).0
+}
+
+/// Penetration found by [epa].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Penetration {
+    /// Unit direction from A to B along which the shapes are separated the quickest.
+    pub normal: Vec3<f32>,
+    /// Distance B has to move along `normal` to separate the shapes.
+    pub depth: f32,
+    /// Deepest point of A inside B.
+    pub point_a: Vec3<f32>,
+    /// Deepest point of B inside A.
+    pub point_b: Vec3<f32>,
+}
+
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f32 = 1e-4;
+
+struct Face {
+    vertices: [usize; 3],
+    normal: Vec3<f32>,
+    distance: f32,
+}
+
+impl Face {
+    /// Degenerate faces have no normal, they are kept to close the polytope but get an infinite
+    /// distance so that they are never picked as the closest face.
+    fn new(vertices: [usize; 3], points: &[SupportPoint]) -> Self {
+        let [a, b, c] = vertices.map(|i| points[i].w);
+        match (b - a).cross(c - a).try_normalize() {
+            Some(normal) => Self {
+                vertices,
+                normal,
+                distance: normal.dot(a),
+            },
+            None => Self {
+                vertices,
+                normal: Vec3::ZERO,
+                distance: f32::INFINITY,
+            },
+        }
+    }
+
+    fn is_degenerate(&self) -> bool {
+        self.distance == f32::INFINITY
+    }
+}
+
+/// Grows a GJK simplex that touches the origin into a tetrahedron that encloses it.
+fn blow_up(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+    simplex: &mut Vec<SupportPoint>,
+) -> bool {
+    const AXES: [Vec3<f32>; 3] = [Vec3::X, Vec3::Y, Vec3::Z];
+    let eps = 1e-10;
+    if simplex.len() == 1 {
+        for dir in AXES.iter().flat_map(|&d| [d, -d]) {
+            let p = minkowski_support(a, b, dir);
+            if (p.w - simplex[0].w).length_squared() > eps {
+                simplex.push(p);
+                break;
+            }
+        }
+    }
+    if simplex.len() == 2 {
+        let d = (simplex[1].w - simplex[0].w).normalize();
+        let mut dir = d.any_orthonormal();
+        let step = crate::math::Quat::from_axis_angle(d, std::f32::consts::FRAC_PI_3);
+        for _ in 0..6 {
+            let p = minkowski_support(a, b, dir);
+            if (p.w - simplex[0].w).cross(d).length_squared() > eps {
+                simplex.push(p);
+                break;
+            }
+            dir = step.rotate(dir);
+        }
+    }
+    if simplex.len() == 3 {
+        let n = (simplex[1].w - simplex[0].w).cross(simplex[2].w - simplex[0].w);
+        for dir in [n, -n] {
+            let p = minkowski_support(a, b, dir);
+            if (p.w - simplex[0].w).dot(n).abs() > eps {
+                simplex.push(p);
+                break;
+            }
+        }
+    }
+    simplex.len() == 4
+}
+
+/// Expanding Polytope Algorithm, finds how deep two intersecting convex shapes overlap.
+///
+/// Returns `None` if the shapes do not overlap or only touch in a way that has no volume, or if
+/// the polytope degenerates so that no face has a normal. The normal is a unit vector
+/// otherwise, also for these poses where the polytope grows degenerate faces:
+///
+/// ```
+/// use rigid_body_physics_engine::math::{Quat, Transform, Vec3};
+/// use rigid_body_physics_engine::narrowphase::{epa, Transformed};
+/// use rigid_body_physics_engine::shape::Shape;
+/// let cube = Shape::Box { half_extents: Vec3::splat(0.5) }.to_mesh(12);
+/// let cylinder = Shape::Cylinder { half_height: 0.5, radius: 0.4 }.to_mesh(12);
+/// let poses = [
+///     (&cube, [0.42507848, -0.39632154, 0.0069250106], [0.384398, 0.28177026, -0.11442251, -0.87163717]),
+///     (&cylinder, [0.6367184, -0.08789344, -0.54316616], [0.62895364, 0.38941315, 0.32080334, 0.59148955]),
+/// ];
+/// for (shape, translation, [w, x, y, z]) in poses {
+///     let moved = Transformed {
+///         shape,
+///         transform: Transform::new(Vec3::new(translation), Quat::new(w, x, y, z)),
+///     };
+///     let penetration = epa(&cube, &moved).unwrap();
+///     assert!((penetration.normal.length() - 1.0).abs() < 1e-4);
+///     assert!(penetration.depth > 0.0);
+/// }
+/// ```
+pub fn epa(a: &(impl SupportMap + ?Sized), b: &(impl SupportMap + ?Sized)) -> Option<Penetration> {
+    let (result, mut points) = gjk_simplex(a, b);
+    if result != GjkResult::Intersecting || !blow_up(a, b, &mut points) {
+        return None;
+    }
+
+    let centroid = points.iter().map(|p| p.w).sum::<Vec3<f32>>() * 0.25;
+    let mut faces: Vec<Face> = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]]
+        .into_iter()
+        .map(|[i, j, k]| {
+            let face = Face::new([i, j, k], &points);
+            if face.normal.dot(points[i].w - centroid) < 0.0 {
+                Face::new([i, k, j], &points)
+            } else {
+                face
+            }
+        })
+        .collect();
+
+    for _ in 0..EPA_MAX_ITERATIONS {
+        let closest = faces
+            .iter()
+            .enumerate()
+            .min_by(|(_, f), (_, g)| f.distance.total_cmp(&g.distance))
+            .map(|(i, _)| i)?;
+        let face = &faces[closest];
+        if face.is_degenerate() {
+            return None;
+        }
+        let p = minkowski_support(a, b, face.normal);
+        if p.w.dot(face.normal) - face.distance <= EPA_TOLERANCE * face.distance.max(1.0) {
+            break;
+        }
+
+        // Remove the faces that see the new point and stitch the hole with new faces. Only the
+        // faces connected to the closest one are removed: rounding can make faces elsewhere see
+        // the point as well, and removing them would leave a second hole.
+        let sees = |f: &Face| f.normal.dot(p.w - points[f.vertices[0]].w) > 0.0;
+        let mut removed = vec![false; faces.len()];
+        removed[closest] = true;
+        let mut stack = vec![closest];
+        while let Some(i) = stack.pop() {
+            let [u, v, w] = faces[i].vertices;
+            for (u, v) in [(u, v), (v, w), (w, u)] {
+                let neighbor = faces.iter().position(|f| {
+                    (0..3).any(|e| (f.vertices[e], f.vertices[(e + 1) % 3]) == (v, u))
+                });
+                if let Some(j) = neighbor.filter(|&j| !removed[j] && sees(&faces[j])) {
+                    removed[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        points.push(p);
+        let new = points.len() - 1;
+        let mut horizon: Vec<(usize, usize)> = Vec::new();
+        let mut removed = removed.into_iter();
+        faces.retain(|f| {
+            if !removed.next().unwrap() {
+                return true;
+            }
+            for e in 0..3 {
+                let edge = (f.vertices[e], f.vertices[(e + 1) % 3]);
+                if let Some(i) = horizon.iter().position(|&(u, v)| (v, u) == edge) {
+                    horizon.swap_remove(i);
+                } else {
+                    horizon.push(edge);
+                }
+            }
+            false
+        });
+        faces.extend(
+            horizon
+                .into_iter()
+                .map(|(u, v)| Face::new([u, v, new], &points)),
+        );
+    }
+
+    let face = faces
+        .iter()
+        .min_by(|f, g| f.distance.total_cmp(&g.distance))
+        .filter(|f| !f.is_degenerate())?;
+    let [i, j, k] = face.vertices;
+    let weights = barycentric(
+        face.normal * face.distance,
+        points[i].w,
+        points[j].w,
+        points[k].w,
+    );
+    let combine = |f: fn(&SupportPoint) -> Vec3<f32>| {
+        f(&points[i]) * weights[0] + f(&points[j]) * weights[1] + f(&points[k]) * weights[2]
+    };
+    Some(Penetration {
+        normal: face.normal,
+        depth: face.distance.max(0.0),
+        point_a: combine(|p| p.a),
+        point_b: combine(|p| p.b),
+    })
+}
+
+/// Barycentric coordinates of `p` projected onto the plane of triangle `abc`.
+pub(crate) fn barycentric(p: Vec3<f32>, a: Vec3<f32>, b: Vec3<f32>, c: Vec3<f32>) -> [f32; 3] {
+    let (v0, v1, v2) = (b - a, c - a, p - a);
+    let (d00, d01, d11) = (v0.dot(v0), v0.dot(v1), v1.dot(v1));
+    let (d20, d21) = (v2.dot(v0), v2.dot(v1));
+    let denom = d00 * d11 - d01 * d01;
+    if denom.abs() <= f32::EPSILON {
+        return [1.0, 0.0, 0.0];
+    }
+    let v = (d11 * d20 - d01 * d21) / denom;
+    let w = (d00 * d21 - d01 * d20) / denom;
+    [1.0 - v - w, v, w]
+}
+
+/// One point of contact between two shapes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactPoint {
+    /// Contact point on the surface of A, in world space.
+    pub point_a: Vec3<f32>,
+    /// Contact point on the surface of B, in world space.
+    pub point_b: Vec3<f32>,
+    /// Penetration along the normal, negative if the shapes are still apart.
+    pub depth: f32,
+}
+
+/// Contact between two shapes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactManifold {
+    /// Unit normal pointing from A to B.
+    pub normal: Vec3<f32>,
+    /// Up to four points spanning the contact area.
+    pub points: Vec<ContactPoint>,
+}
+
+impl ContactManifold {
+    /// Largest penetration of all points.
+    pub fn depth(&self) -> f32 {
+        self.points
+            .iter()
+            .map(|p| p.depth)
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// The same contact seen from B.
+    pub fn flipped(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            points: self
+                .points
+                .iter()
+                .map(|p| ContactPoint {
+                    point_a: p.point_b,
+                    point_b: p.point_a,
+                    depth: p.depth,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Contact manifold between two bodies of a world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+    pub manifold: ContactManifold,
+}
+
+/// Vertices closer than this to the support plane count as part of the contact feature. The
+/// contact margin is used instead when it is larger, so that a face resting slightly tilted
+/// still gets a full patch of speculative points instead of rocking from edge to edge.
+const FEATURE_TOLERANCE: f32 = 1e-3;
+
+/// Generates the contact manifold of two convex shapes that are closer than `margin`.
+///
+/// GJK and EPA find the contact normal, then the features of both shapes facing each other are
+/// clipped against one another so that resting faces get a full contact patch.
+pub fn contact_manifold(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+    margin: f32,
+) -> Option<ContactManifold> {
+    let (normal, fallback) = contact_normal(a, b, margin)?;
+    let points = clip_features(a, b, normal, margin).unwrap_or_else(|| vec![fallback]);
+    Some(ContactManifold { normal, points })
+}
+
+/// Normal of the contact between two convex shapes closer than `margin`, and their closest or
+/// deepest points.
+fn contact_normal(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+    margin: f32,
+) -> Option<(Vec3<f32>, ContactPoint)> {
+    match gjk(a, b) {
+        GjkResult::Separated {
+            distance,
+            point_a,
+            point_b,
+        } => {
+            if distance > margin {
+                return None;
+            }
+            let normal = (point_b - point_a) / distance;
+            let point = ContactPoint {
+                point_a,
+                point_b,
+                depth: -distance,
+            };
+            Some((normal, point))
+        }
+        GjkResult::Intersecting => {
+            let p = epa(a, b)?;
+            let point = ContactPoint {
+                point_a: p.point_a,
+                point_b: p.point_b,
+                depth: p.depth,
+            };
+            Some((p.normal, point))
+        }
+    }
+}
+
+/// Contact between a convex shape and face `face` of a triangle mesh placed at `transform`.
+///
+/// Normals that the mesh can not produce at the touched feature, see
+/// [TriMeshCollider::is_admissible], are replaced by the face normal so that bodies slide
+/// smoothly across the edges between faces.
+fn triangle_manifold(
+    a: &(impl SupportMap + ?Sized),
+    mesh: &TriMeshCollider,
+    face: usize,
+    transform: Transform<f32>,
+    margin: f32,
+) -> Option<ContactManifold> {
+    let triangle = mesh.triangle(face);
+    let face_normal = transform.transform_vector(triangle.normal);
+    let b = Transformed {
+        shape: &triangle,
+        transform,
+    };
+    let (normal, fallback) = contact_normal(a, &b, margin)?;
+    let admissible = mesh.is_admissible(
+        face,
+        transform.inverse_transform_point(fallback.point_b),
+        transform.inverse_transform_vector(-normal),
+    );
+    if admissible {
+        let points = clip_features(a, &b, normal, margin).unwrap_or_else(|| vec![fallback]);
+        return Some(ContactManifold { normal, points });
+    }
+    let normal = -face_normal;
+    let points = clip_features(a, &b, normal, margin)?;
+    Some(ContactManifold { normal, points })
+}
+
+/// Polygon of support points, ordered counter clockwise around `normal`.
+fn feature_polygon(points: Vec<Vec3<f32>>, normal: Vec3<f32>) -> Vec<Vec3<f32>> {
+    let u = normal.any_orthonormal();
+    let v = normal.cross(u);
+    let mut projected: Vec<(f32, f32, Vec3<f32>)> = points
+        .into_iter()
+        .map(|p| (p.dot(u), p.dot(v), p))
+        .collect();
+    projected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    projected.dedup_by(|a, b| (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6);
+    if projected.len() < 3 {
+        return projected.into_iter().map(|p| p.2).collect();
+    }
+    // Andrew's monotone chain.
+    let cross =
+        |o: &(f32, f32, Vec3<f32>), a: &(f32, f32, Vec3<f32>), b: &(f32, f32, Vec3<f32>)| {
+            (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+        };
+    let mut hull: Vec<(f32, f32, Vec3<f32>)> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        let iter: Box<dyn Iterator<Item = &(f32, f32, Vec3<f32>)>> = if pass == 0 {
+            Box::new(projected.iter())
+        } else {
+            Box::new(projected.iter().rev())
+        };
+        for p in iter {
+            while hull.len() >= start + 2
+                && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+    }
+    hull.into_iter().map(|p| p.2).collect()
+}
+
+fn clip_features(
+    a: &(impl SupportMap + ?Sized),
+    b: &(impl SupportMap + ?Sized),
+    normal: Vec3<f32>,
+    margin: f32,
+) -> Option<Vec<ContactPoint>> {
+    let tolerance = margin.max(FEATURE_TOLERANCE);
+    let feature_a = feature_polygon(a.support_points(normal, tolerance), normal);
+    let feature_b = feature_polygon(b.support_points(-normal, tolerance), normal);
+    // The feature with more vertices is the reference face, the other one gets clipped.
+    let (reference, incident, flip) = if feature_a.len() >= feature_b.len() {
+        (feature_a, feature_b, false)
+    } else {
+        (feature_b, feature_a, true)
+    };
+    if reference.len() < 3 {
+        return None;
+    }
+
+    let mut clipped = incident;
+    let n = reference.len();
+    for i in 0..n {
+        let start = reference[i];
+        let side = (reference[(i + 1) % n] - start).cross(normal);
+        let distance = |p: Vec3<f32>| (p - start).dot(side);
+        let input = std::mem::take(&mut clipped);
+        if input.len() == 1 {
+            if distance(input[0]) <= 0.0 {
+                clipped = input;
+            }
+            continue;
+        }
+        let m = input.len();
+        for j in 0..m {
+            // Segments are handled as a single edge, not as a closed polygon.
+            if m == 2 && j == 1 {
+                if distance(input[1]) <= 0.0 {
+                    clipped.push(input[1]);
+                }
+                break;
+            }
+            let (p, q) = (input[j], input[(j + 1) % m]);
+            let (dp, dq) = (distance(p), distance(q));
+            if dp <= 0.0 {
+                clipped.push(p);
+            }
+            if (dp < 0.0) != (dq < 0.0) && dp != dq {
+                clipped.push(p.lerp(q, dp / (dp - dq)));
+            }
+        }
+    }
+
+    let mut unique: Vec<Vec3<f32>> = Vec::with_capacity(clipped.len());
+    for p in clipped {
+        if unique.iter().all(|q| (*q - p).length_squared() > 1e-10) {
+            unique.push(p);
+        }
+    }
+
+    // The reference plane is perpendicular to the normal through the reference support point.
+    let plane = reference[0].dot(normal);
+    let mut points: Vec<ContactPoint> = unique
+        .into_iter()
+        .filter_map(|p| {
+            let (depth, on_reference) = if flip {
+                (p.dot(normal) - plane, p - normal * (p.dot(normal) - plane))
+            } else {
+                (plane - p.dot(normal), p + normal * (plane - p.dot(normal)))
+            };
+            if depth < -margin {
+                return None;
+            }
+            Some(if flip {
+                ContactPoint {
+                    point_a: p,
+                    point_b: on_reference,
+                    depth,
+                }
+            } else {
+                ContactPoint {
+                    point_a: on_reference,
+                    point_b: p,
+                    depth,
+                }
+            })
+        })
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    reduce_points(&mut points, normal);
+    Some(points)
+}
+
+/// Keeps at most four points: the deepest one and those spanning the largest area with it.
+fn reduce_points(points: &mut Vec<ContactPoint>, normal: Vec3<f32>) {
+    if points.len() <= 4 {
+        return;
+    }
+    let position = |p: &ContactPoint| p.point_b;
+    let pick = |points: &[ContactPoint], score: &dyn Fn(&ContactPoint) -> f32| {
+        (0..points.len())
+            .max_by(|&i, &j| score(&points[i]).total_cmp(&score(&points[j])))
+            .unwrap()
+    };
+    let mut kept = Vec::with_capacity(4);
+    kept.push(points.swap_remove(pick(points, &|p| p.depth)));
+    let p0 = position(&kept[0]);
+    kept.push(points.swap_remove(pick(points, &|p| (position(p) - p0).length_squared())));
+    let p1 = position(&kept[1]);
+    let area = |p: &ContactPoint| (p1 - p0).cross(position(p) - p0).dot(normal);
+    kept.push(points.swap_remove(pick(points, &|p| area(p).abs())));
+    let side = area(&kept[2]).signum();
+    kept.push(points.swap_remove(pick(points, &|p| -side * area(p))));
+    *points = kept;
+}
+
+/// Contacts between the colliders of two bodies, one manifold per pair of touching convex parts
+/// or per touched face of a triangle mesh.
+///
+/// Pairs of primitives use specialized routines where there is one, see [Shape].
+///
+/// ```
+/// use std::sync::Arc;
+/// use rigid_body_physics_engine::body::RigidBody;
+/// use rigid_body_physics_engine::collider::Collider;
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::narrowphase::collide;
+/// use rigid_body_physics_engine::shape::Shape;
+/// let ground = Shape::Plane { normal: Vec3::new([0.0, 1.0, 0.0]) };
+/// let ground = RigidBody::new_static(Arc::new(ground.to_mesh(4)))
+///     .with_collider(Collider::Primitive(ground));
+/// let mut ball = RigidBody::from_shape(Shape::Sphere { radius: 0.5 }, 1.0).unwrap();
+/// ball.position = Vec3::new([0.0, 0.49, 0.0]);
+/// let manifolds = collide(&ball, &ground, 0.02);
+/// assert_eq!(manifolds[0].normal, Vec3::new([0.0, -1.0, 0.0]));
+/// assert!((manifolds[0].depth() - 0.01).abs() < 1e-5);
+/// ```
+pub fn collide(a: &RigidBody, b: &RigidBody, margin: f32) -> Vec<ContactManifold> {
+    let plane = |collider: &Collider| match collider {
+        Collider::Primitive(Shape::Plane { normal }) => Some(*normal),
+        _ => None,
+    };
+    let unbounded = |collider: &Collider| {
+        matches!(
+            collider,
+            Collider::TriMesh(_) | Collider::Primitive(Shape::Plane { .. })
+        )
+    };
+    if unbounded(&a.collider) && unbounded(&b.collider) {
+        return Vec::new();
+    }
+    if let Some(normal) = plane(&b.collider) {
+        return collide_plane(a, b.transform(), normal, margin);
+    }
+    if let Some(normal) = plane(&a.collider) {
+        return collide_plane(b, a.transform(), normal, margin)
+            .iter()
+            .map(ContactManifold::flipped)
+            .collect();
+    }
+    match (&a.collider, &b.collider) {
+        (_, Collider::TriMesh(mesh)) => collide_trimesh(a, b, mesh, margin),
+        (Collider::TriMesh(mesh), _) => collide_trimesh(b, a, mesh, margin)
+            .iter()
+            .map(ContactManifold::flipped)
+            .collect(),
+        (Collider::Primitive(shape_a), Collider::Primitive(shape_b)) => {
+            primitive_manifold(shape_a, a.transform(), shape_b, b.transform(), margin)
+                .into_iter()
+                .collect()
+        }
+        _ => collide_convex(a, b, margin),
+    }
+}
+
+fn collide_convex(a: &RigidBody, b: &RigidBody, margin: f32) -> Vec<ContactManifold> {
+    let (transform_a, transform_b) = (a.transform(), b.transform());
+    let parts_b: Vec<_> = b
+        .collider
+        .convex_parts()
+        .into_iter()
+        .map(|(shape, aabb)| (shape, aabb.transformed(&transform_b).fatten(margin)))
+        .collect();
+    let mut manifolds = Vec::new();
+    for (shape_a, aabb_a) in a.collider.convex_parts() {
+        let aabb_a = aabb_a.transformed(&transform_a);
+        for &(shape_b, aabb_b) in &parts_b {
+            if !aabb_a.overlaps(&aabb_b) {
+                continue;
+            }
+            let shape_a = Transformed {
+                shape: shape_a,
+                transform: transform_a,
+            };
+            let shape_b = Transformed {
+                shape: shape_b,
+                transform: transform_b,
+            };
+            manifolds.extend(contact_manifold(&shape_a, &shape_b, margin));
+        }
+    }
+    manifolds
+}
+
+/// Contacts of the convex parts of `a` with the faces of `mesh`, the collider of `b`.
+fn collide_trimesh(
+    a: &RigidBody,
+    b: &RigidBody,
+    mesh: &TriMeshCollider,
+    margin: f32,
+) -> Vec<ContactManifold> {
+    let (transform_a, transform_b) = (a.transform(), b.transform());
+    // Boxes of the parts of A in the space of the mesh.
+    let a_to_b = transform_b.inverse() * transform_a;
+    let mut manifolds = Vec::new();
+    for (shape, aabb) in a.collider.convex_parts() {
+        let shape = Transformed {
+            shape,
+            transform: transform_a,
+        };
+        mesh.query(&aabb.transformed(&a_to_b).fatten(margin), |face| {
+            manifolds.extend(triangle_manifold(&shape, mesh, face, transform_b, margin));
+        });
+    }
+    manifolds
+}
+
+/// Contacts of the convex parts of `a` with the half space below the plane with body space
+/// `normal` through the origin of `transform`.
+fn collide_plane(
+    a: &RigidBody,
+    transform: Transform<f32>,
+    normal: Vec3<f32>,
+    margin: f32,
+) -> Vec<ContactManifold> {
+    let normal = transform.transform_vector(normal);
+    let offset = normal.dot(transform.translation);
+    let transform_a = a.transform();
+    let tolerance = margin.max(FEATURE_TOLERANCE);
+    a.collider
+        .convex_parts()
+        .into_iter()
+        .filter_map(|(shape, _)| {
+            let shape = Transformed {
+                shape,
+                transform: transform_a,
+            };
+            let mut points: Vec<ContactPoint> = shape
+                .support_points(-normal, tolerance)
+                .into_iter()
+                .filter_map(|p| {
+                    let distance = p.dot(normal) - offset;
+                    (distance <= margin).then(|| ContactPoint {
+                        point_a: p,
+                        point_b: p - normal * distance,
+                        depth: -distance,
+                    })
+                })
+                .collect();
+            if points.is_empty() {
+                return None;
+            }
+            reduce_points(&mut points, normal);
+            Some(ContactManifold {
+                normal: -normal,
+                points,
+            })
+        })
+        .collect()
+}
+
+/// Face axes are preferred over edge axes of a box pair unless these separate the boxes by this
+/// much more, since faces give the more stable manifolds.
+const EDGE_AXIS_BIAS: f32 = 1e-3;
+
+/// Contact between two primitives placed by their transforms.
+fn primitive_manifold(
+    a: &Shape,
+    transform_a: Transform<f32>,
+    b: &Shape,
+    transform_b: Transform<f32>,
+    margin: f32,
+) -> Option<ContactManifold> {
+    let (center_a, center_b) = (transform_a.translation, transform_b.translation);
+    match (*a, *b) {
+        (Shape::Sphere { radius: ra }, Shape::Sphere { radius: rb }) => {
+            sphere_sphere(center_a, ra, center_b, rb, margin)
+        }
+        (Shape::Sphere { radius }, Shape::Box { half_extents }) => {
+            sphere_box(center_a, radius, transform_b, half_extents, margin)
+        }
+        (Shape::Box { half_extents }, Shape::Sphere { radius }) => {
+            sphere_box(center_b, radius, transform_a, half_extents, margin).map(|m| m.flipped())
+        }
+        (Shape::Box { half_extents: ha }, Shape::Box { half_extents: hb }) => {
+            box_box(transform_a, ha, transform_b, hb, margin)
+        }
+        (
+            Shape::Capsule {
+                half_height: ha,
+                radius: ra,
+            },
+            Shape::Capsule {
+                half_height: hb,
+                radius: rb,
+            },
+        ) => capsule_capsule((transform_a, ha, ra), (transform_b, hb, rb), margin),
+        _ => {
+            let a = Transformed {
+                shape: a,
+                transform: transform_a,
+            };
+            let b = Transformed {
+                shape: b,
+                transform: transform_b,
+            };
+            contact_manifold(&a, &b, margin)
+        }
+    }
+}
+
+fn sphere_sphere(
+    center_a: Vec3<f32>,
+    radius_a: f32,
+    center_b: Vec3<f32>,
+    radius_b: f32,
+    margin: f32,
+) -> Option<ContactManifold> {
+    let d = center_b - center_a;
+    let gap = d.length() - radius_a - radius_b;
+    if gap > margin {
+        return None;
+    }
+    let normal = d.try_normalize().unwrap_or(Vec3::Y);
+    Some(ContactManifold {
+        normal,
+        points: vec![ContactPoint {
+            point_a: center_a + normal * radius_a,
+            point_b: center_b - normal * radius_b,
+            depth: -gap,
+        }],
+    })
+}
+
+/// Contact of a sphere with a box, the normal points from the sphere to the box.
+fn sphere_box(
+    center: Vec3<f32>,
+    radius: f32,
+    transform: Transform<f32>,
+    half_extents: Vec3<f32>,
+    margin: f32,
+) -> Option<ContactManifold> {
+    let local = transform.inverse_transform_point(center);
+    let inside = (0..3).all(|i| local[i].abs() <= half_extents[i]);
+    // Normal in box space, closest point on the box surface and signed distance to it.
+    let (normal, surface, distance) = if inside {
+        // Leave through the nearest face.
+        let depth = |i: usize| half_extents[i] - local[i].abs();
+        let axis = (0..3)
+            .min_by(|&i, &j| depth(i).total_cmp(&depth(j)))
+            .unwrap();
+        let side = if local[axis] < 0.0 { -1.0 } else { 1.0 };
+        let mut surface = local;
+        surface[axis] = side * half_extents[axis];
+        let mut normal = Vec3::ZERO;
+        normal[axis] = -side;
+        (normal, surface, -depth(axis))
+    } else {
+        let surface = local.max(-half_extents).min(half_extents);
+        let d = surface - local;
+        let distance = d.length();
+        (d / distance, surface, distance)
+    };
+    let gap = distance - radius;
+    if gap > margin {
+        return None;
+    }
+    let normal = transform.transform_vector(normal);
+    Some(ContactManifold {
+        normal,
+        points: vec![ContactPoint {
+            point_a: center + normal * radius,
+            point_b: transform.transform_point(surface),
+            depth: -gap,
+        }],
+    })
+}
+
+/// Contact of two boxes by the separating axis test over the face normals of both boxes and
+/// the cross products of their edges.
+fn box_box(
+    transform_a: Transform<f32>,
+    half_a: Vec3<f32>,
+    transform_b: Transform<f32>,
+    half_b: Vec3<f32>,
+    margin: f32,
+) -> Option<ContactManifold> {
+    let axes = |t: &Transform<f32>| {
+        let r = t.rotation.to_mat3();
+        [0, 1, 2].map(|i| r.col(i))
+    };
+    let (axes_a, axes_b) = (axes(&transform_a), axes(&transform_b));
+    let d = transform_b.translation - transform_a.translation;
+    let project = |axes: &[Vec3<f32>; 3], half: Vec3<f32>, axis: Vec3<f32>| {
+        (0..3)
+            .map(|i| half[i] * axes[i].dot(axis).abs())
+            .sum::<f32>()
+    };
+    let separation = |axis: Vec3<f32>| {
+        d.dot(axis).abs() - project(&axes_a, half_a, axis) - project(&axes_b, half_b, axis)
+    };
+
+    let mut best = (f32::NEG_INFINITY, Vec3::Y);
+    for axis in axes_a.into_iter().chain(axes_b) {
+        let s = separation(axis);
+        if s > margin {
+            return None;
+        }
+        if s > best.0 {
+            best = (s, axis);
+        }
+    }
+    let mut edge = false;
+    for a in axes_a {
+        for b in axes_b {
+            let cross = a.cross(b);
+            // Nearly parallel edges are covered by the face axes.
+            if cross.length_squared() < 1e-6 {
+                continue;
+            }
+            let axis = cross.normalize();
+            let s = separation(axis);
+            if s > margin {
+                return None;
+            }
+            if s > best.0 + EDGE_AXIS_BIAS {
+                best = (s, axis);
+                edge = true;
+            }
+        }
+    }
+
+    let (s, axis) = best;
+    let normal = if d.dot(axis) < 0.0 { -axis } else { axis };
+    let shape_a = Transformed {
+        shape: &Shape::Box {
+            half_extents: half_a,
+        },
+        transform: transform_a,
+    };
+    let shape_b = Transformed {
+        shape: &Shape::Box {
+            half_extents: half_b,
+        },
+        transform: transform_b,
+    };
+    if !edge {
+        if let Some(points) = clip_features(&shape_a, &shape_b, normal, margin) {
+            return Some(ContactManifold { normal, points });
+        }
+    }
+    // Edge against edge, the contact is between the closest points of the two edges.
+    let feature_a = shape_a.support_points(normal, FEATURE_TOLERANCE);
+    let feature_b = shape_b.support_points(-normal, FEATURE_TOLERANCE);
+    let (point_a, point_b) = match (feature_a.as_slice(), feature_b.as_slice()) {
+        (&[a0, a1], &[b0, b1]) => closest_on_segments(a0, a1, b0, b1),
+        _ => (shape_a.support(normal), shape_b.support(-normal)),
+    };
+    Some(ContactManifold {
+        normal,
+        points: vec![ContactPoint {
+            point_a,
+            point_b,
+            depth: -s,
+        }],
+    })
+}
+
+/// Contact of two capsules given as transform, half height and radius.
+fn capsule_capsule(
+    (transform_a, half_a, radius_a): (Transform<f32>, f32, f32),
+    (transform_b, half_b, radius_b): (Transform<f32>, f32, f32),
+    margin: f32,
+) -> Option<ContactManifold> {
+    let segment = |t: Transform<f32>, half: f32| {
+        let up = t.transform_vector(Vec3::Y) * half;
+        (t.translation - up, t.translation + up)
+    };
+    let ((a0, a1), (b0, b1)) = (segment(transform_a, half_a), segment(transform_b, half_b));
+    let (p, q) = closest_on_segments(a0, a1, b0, b1);
+    let gap = p.distance(q) - radius_a - radius_b;
+    if gap > margin {
+        return None;
+    }
+    let (da, db) = (a1 - a0, b1 - b0);
+    let normal = (q - p)
+        .try_normalize()
+        .or_else(|| da.cross(db).try_normalize())
+        .unwrap_or(Vec3::Y);
+    let point = |p: Vec3<f32>, q: Vec3<f32>| ContactPoint {
+        point_a: p + normal * radius_a,
+        point_b: q - normal * radius_b,
+        depth: radius_a + radius_b - (q - p).dot(normal),
+    };
+    let mut points = vec![point(p, q)];
+
+    // Capsules lying side by side touch along a line, keep both ends of it.
+    let (la, lb) = (da.length_squared(), db.length_squared());
+    if la > 0.0 && lb > 0.0 && da.cross(db).length_squared() < 1e-6 * la * lb {
+        let along = |x: Vec3<f32>| (x - a0).dot(da) / la;
+        let (s0, s1) = (along(b0), along(b1));
+        let (from, to) = (s0.min(s1).max(0.0), s0.max(s1).min(1.0));
+        if to - from > 1e-3 {
+            points = [from, to]
+                .into_iter()
+                .map(|s| {
+                    let p = a0 + da * s;
+                    let t = ((p - b0).dot(db) / lb).clamp(0.0, 1.0);
+                    point(p, b0 + db * t)
+                })
+                .filter(|c| c.depth >= -margin)
+                .collect();
+        }
+    }
+    Some(ContactManifold { normal, points })
+}
+
+/// Closest points of the segments `p0 p1` and `q0 q1`.
+fn closest_on_segments(
+    p0: Vec3<f32>,
+    p1: Vec3<f32>,
+    q0: Vec3<f32>,
+    q1: Vec3<f32>,
+) -> (Vec3<f32>, Vec3<f32>) {
+    const EPSILON: f32 = 1e-12;
+    let (d1, d2, r) = (p1 - p0, q1 - q0, p0 - q0);
+    let (a, e, f) = (d1.dot(d1), d2.dot(d2), d2.dot(r));
+    let (s, t) = if a <= EPSILON && e <= EPSILON {
+        (0.0, 0.0)
+    } else if a <= EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let s = if denom > EPSILON {
+                ((b * f - c * e) / denom).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / a).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (p0 + d1 * s, q0 + d2 * t)
+}
@@ -0,0 +1,424 @@
+use crate::body::RigidBody;
+use crate::math::{Mat3, Quat, Transform, Vec3};
+use crate::stl::IndexedMesh;
+use crate::world::{BodyHandle, World};
+use gxhash::{HashSet, HashSetExt};
+
+/// Perspective camera looking down its local -Z axis with +Y up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Vec3<f32>,
+    pub orientation: Quat<f32>,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+    /// Distance of the near clipping plane, geometry closer than this is cut off.
+    pub near: f32,
+}
+
+impl Camera {
+    /// Camera at `position` facing `target`, with `up` pointing roughly upwards on screen.
+    pub fn look_at(position: Vec3<f32>, target: Vec3<f32>, up: Vec3<f32>) -> Self {
+        let forward = (target - position).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+        Self {
+            position,
+            orientation: Quat::from_mat3(&Mat3::from_cols([right, up, -forward])),
+            fov_y: 60f32.to_radians(),
+            near: 0.05,
+        }
+    }
+
+    /// Unit vector the camera looks along.
+    pub fn forward(&self) -> Vec3<f32> {
+        self.orientation.rotate(-Vec3::Z)
+    }
+
+    /// World space point in camera space.
+    pub fn to_view(&self, p: Vec3<f32>) -> Vec3<f32> {
+        self.orientation.inverse_rotate(p - self.position)
+    }
+
+    /// Screen position in pixels of a camera space point together with its distance along the
+    /// view direction, or `None` if it is not in front of the near plane.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::math::Vec3;
+    /// use rigid_body_physics_engine::render::Camera;
+    /// let camera = Camera::look_at(Vec3::new([0.0, 0.0, 5.0]), Vec3::ZERO, Vec3::Y);
+    /// let center = camera.project(camera.to_view(Vec3::ZERO), 640, 480).unwrap();
+    /// assert_eq!(center, Vec3::new([320.0, 240.0, 5.0]));
+    /// assert!(camera.project(camera.to_view(Vec3::new([0.0, 0.0, 6.0])), 640, 480).is_none());
+    /// ```
+    pub fn project(&self, view: Vec3<f32>, width: u32, height: u32) -> Option<Vec3<f32>> {
+        let depth = -view.z();
+        if depth < self.near {
+            return None;
+        }
+        let (width, height) = (width as f32, height as f32);
+        let focal = 0.5 * height / (0.5 * self.fov_y).tan();
+        Some(Vec3::new([
+            0.5 * width + focal * view.x() / depth,
+            0.5 * height - focal * view.y() / depth,
+            depth,
+        ]))
+    }
+}
+
+/// Unit normal of face `i` in mesh space, from the stored normal or from the winding if the
+/// file left it empty.
+pub fn face_normal(mesh: &IndexedMesh, i: usize) -> Vec3<f32> {
+    let face = &mesh.faces[i];
+    face.normal.try_normalize().unwrap_or_else(|| {
+        let [a, b, c] = face.vertices.map(|v| mesh.vertices[v]);
+        (b - a).cross(c - a).try_normalize().unwrap_or(Vec3::ZERO)
+    })
+}
+
+/// Screen space line segments of the edges of all faces of `mesh` that face the camera, after
+/// placing the mesh with `transform`. Edges are cut at the near plane.
+///
+/// ```
+/// use rigid_body_physics_engine::math::{Transform, Vec3};
+/// use rigid_body_physics_engine::render::{wireframe, Camera};
+/// use rigid_body_physics_engine::shape::Shape;
+/// let cube = Shape::Box { half_extents: Vec3::splat(0.5) }.to_mesh(4);
+/// let camera = Camera::look_at(Vec3::new([0.0, 0.0, 5.0]), Vec3::ZERO, Vec3::Y);
+/// // Only the front face is visible: its outline and the diagonal between its two triangles.
+/// let lines = wireframe(&cube, &Transform::IDENTITY, &camera, 640, 480);
+/// assert_eq!(lines.len(), 5);
+/// ```
+pub fn wireframe(
+    mesh: &IndexedMesh,
+    transform: &Transform<f32>,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+) -> Vec<[[f32; 2]; 2]> {
+    let view: Vec<Vec3<f32>> = mesh
+        .vertices
+        .iter()
+        .map(|&v| camera.to_view(transform.transform_point(v)))
+        .collect();
+    // Faces are culled in mesh space, where the stored normals live.
+    let camera_in_mesh = transform.inverse_transform_point(camera.position);
+    let mut edges = HashSet::new();
+    for (i, face) in mesh.faces.iter().enumerate() {
+        let corner = mesh.vertices[face.vertices[0]];
+        if face_normal(mesh, i).dot(camera_in_mesh - corner) <= 0.0 {
+            continue;
+        }
+        for k in 0..3 {
+            let (a, b) = (face.vertices[k], face.vertices[(k + 1) % 3]);
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+    let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+    edges.sort_unstable();
+    edges
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let (a, b) = clip_near(view[a], view[b], camera.near)?;
+            let a = camera.project(a, width, height)?;
+            let b = camera.project(b, width, height)?;
+            Some([[a.x(), a.y()], [b.x(), b.y()]])
+        })
+        .collect()
+}
+
+/// The part of the camera space segment `a b` in front of the near plane.
+fn clip_near(a: Vec3<f32>, b: Vec3<f32>, near: f32) -> Option<(Vec3<f32>, Vec3<f32>)> {
+    let (da, db) = (-a.z() - near, -b.z() - near);
+    let cut = || {
+        let mut p = a.lerp(b, da / (da - db));
+        p[2] = -near;
+        p
+    };
+    match (da >= 0.0, db >= 0.0) {
+        (true, true) => Some((a, b)),
+        (false, false) => None,
+        (true, false) => Some((a, cut())),
+        (false, true) => Some((cut(), b)),
+    }
+}
+
+/// How filled triangles are lit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shading {
+    /// One brightness per face.
+    Flat,
+    /// Brightness computed at the vertices from smoothed normals and blended across the face.
+    #[default]
+    Gouraud,
+}
+
+/// Faces meeting at a vertex at a sharper angle than this keep separate normals under
+/// [Shading::Gouraud], so that boxes keep their edges.
+const CREASE_COS: f32 = 0.5;
+
+/// Directional light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    /// Unit vector pointing towards the light.
+    pub direction: Vec3<f32>,
+    /// Brightness of faces that point away from the light, between 0 and 1.
+    pub ambient: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new([0.4, 1.0, 0.6]).normalize(),
+            ambient: 0.25,
+        }
+    }
+}
+
+impl Light {
+    fn brightness(&self, normal: Vec3<f32>) -> f32 {
+        self.ambient + (1.0 - self.ambient) * normal.dot(self.direction).max(0.0)
+    }
+}
+
+/// Color and depth buffer that triangles are rasterized into on the CPU.
+///
+/// ```
+/// use rigid_body_physics_engine::math::{Transform, Vec3};
+/// use rigid_body_physics_engine::render::{Camera, Framebuffer, Light, Shading};
+/// use rigid_body_physics_engine::shape::Shape;
+/// let cube = Shape::Box { half_extents: Vec3::splat(0.5) }.to_mesh(4);
+/// let camera = Camera::look_at(Vec3::new([0.0, 0.0, 5.0]), Vec3::ZERO, Vec3::Y);
+/// let mut frame = Framebuffer::new(64, 48);
+/// frame.clear([0, 0, 0]);
+/// let light = Light { direction: Vec3::Z, ambient: 0.0 };
+/// frame.draw_mesh(&cube, &Transform::IDENTITY, &camera, [200, 100, 50], &light, Shading::Flat);
+/// // The front face is lit head on, the corner stays background.
+/// assert_eq!(frame.pixel(32, 24), [200, 100, 50]);
+/// assert_eq!(frame.pixel(0, 0), [0, 0, 0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// Packed RGB triplets, row by row.
+    color: Vec<u8>,
+    /// Inverse view depth of the closest surface per pixel, zero where nothing was drawn.
+    inv_depth: Vec<f32>,
+}
+
+/// Colors of dynamic bodies, picked by body index.
+const PALETTE: [[u8; 3]; 8] = [
+    [230, 97, 73],
+    [244, 180, 62],
+    [122, 196, 90],
+    [72, 168, 214],
+    [150, 110, 210],
+    [232, 120, 170],
+    [80, 200, 180],
+    [200, 200, 90],
+];
+
+/// Color of a body: grey for static bodies, a palette color per dynamic body, faded towards blue
+/// while it sleeps.
+pub fn body_color(handle: BodyHandle, body: &RigidBody) -> [u8; 3] {
+    if body.is_static() {
+        return [150, 150, 150];
+    }
+    let color = PALETTE[handle.0 % PALETTE.len()];
+    if body.is_sleeping() {
+        let sleeping = [70, 90, 160];
+        return [0, 1, 2].map(|i| ((color[i] as u16 + 2 * sleeping[i] as u16) / 3) as u8);
+    }
+    color
+}
+
+/// Vertex after projection: screen position, inverse depth and brightness.
+#[derive(Clone, Copy, Debug)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    inv_depth: f32,
+    brightness: f32,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let pixels = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            color: vec![0; pixels * 3],
+            inv_depth: vec![0.0; pixels],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Packed RGB bytes, `3 * width` per row, ready for an RGB24 texture.
+    pub fn pixels(&self) -> &[u8] {
+        &self.color
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let i = 3 * (y as usize * self.width as usize + x as usize);
+        [self.color[i], self.color[i + 1], self.color[i + 2]]
+    }
+
+    /// Fills the color buffer with `color` and resets the depth buffer.
+    pub fn clear(&mut self, color: [u8; 3]) {
+        for pixel in self.color.chunks_exact_mut(3) {
+            pixel.copy_from_slice(&color);
+        }
+        self.inv_depth.fill(0.0);
+    }
+
+    /// Draws every body of `world` in its [body_color] at its interpolated transform.
+    pub fn draw_world(&mut self, world: &World, camera: &Camera, light: &Light, shading: Shading) {
+        for (handle, body) in world.iter() {
+            let transform = world.interpolated_transform(handle);
+            let color = body_color(handle, body);
+            self.draw_mesh(&body.mesh, &transform, camera, color, light, shading);
+        }
+    }
+
+    /// Rasterizes the faces of `mesh` placed by `transform` that face the camera.
+    pub fn draw_mesh(
+        &mut self,
+        mesh: &IndexedMesh,
+        transform: &Transform<f32>,
+        camera: &Camera,
+        color: [u8; 3],
+        light: &Light,
+        shading: Shading,
+    ) {
+        let view: Vec<Vec3<f32>> = mesh
+            .vertices
+            .iter()
+            .map(|&v| camera.to_view(transform.transform_point(v)))
+            .collect();
+        let normals: Vec<Vec3<f32>> = (0..mesh.faces.len())
+            .map(|i| transform.transform_vector(face_normal(mesh, i)))
+            .collect();
+        let corner_normals = match shading {
+            Shading::Flat => None,
+            Shading::Gouraud => Some(smooth_normals(mesh, &normals)),
+        };
+        for (i, face) in mesh.faces.iter().enumerate() {
+            let brightness: [f32; 3] = match &corner_normals {
+                None => [light.brightness(normals[i]); 3],
+                Some(corners) => corners[i].map(|n| light.brightness(n)),
+            };
+            let corners = [0, 1, 2].map(|k| (view[face.vertices[k]], brightness[k]));
+            let polygon = clip_polygon_near(&corners, camera.near);
+            let projected: Vec<ScreenVertex> = polygon
+                .iter()
+                .filter_map(|&(p, brightness)| {
+                    let s = camera.project(p, self.width, self.height)?;
+                    Some(ScreenVertex {
+                        x: s.x(),
+                        y: s.y(),
+                        inv_depth: 1.0 / s.z(),
+                        brightness,
+                    })
+                })
+                .collect();
+            for k in 1..projected.len().saturating_sub(1) {
+                self.fill_triangle([projected[0], projected[k], projected[k + 1]], color);
+            }
+        }
+    }
+
+    fn fill_triangle(&mut self, [a, b, c]: [ScreenVertex; 3], color: [u8; 3]) {
+        let edge = |p: &ScreenVertex, q: &ScreenVertex, x: f32, y: f32| {
+            (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x)
+        };
+        let area = edge(&a, &b, c.x, c.y);
+        // Counter clockwise in the world is clockwise on screen, where y points down.
+        if area >= 0.0 {
+            return;
+        }
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+        let max_x = (a.x.max(b.x).max(c.x).ceil() as i64).min(self.width as i64 - 1);
+        let max_y = (a.y.max(b.y).max(c.y).ceil() as i64).min(self.height as i64 - 1);
+        if max_x < 0 || max_y < 0 {
+            return;
+        }
+        for y in min_y..=max_y as u32 {
+            for x in min_x..=max_x as u32 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    edge(&b, &c, px, py) / area,
+                    edge(&c, &a, px, py) / area,
+                    edge(&a, &b, px, py) / area,
+                ];
+                if weights.iter().any(|&w| w < 0.0) {
+                    continue;
+                }
+                let [wa, wb, wc] = weights;
+                let inv_depth = wa * a.inv_depth + wb * b.inv_depth + wc * c.inv_depth;
+                let i = y as usize * self.width as usize + x as usize;
+                if inv_depth <= self.inv_depth[i] {
+                    continue;
+                }
+                self.inv_depth[i] = inv_depth;
+                let brightness = wa * a.brightness + wb * b.brightness + wc * c.brightness;
+                for (channel, &base) in self.color[3 * i..3 * i + 3].iter_mut().zip(&color) {
+                    *channel = (base as f32 * brightness).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// World space normal of every face corner, averaged over the faces around the vertex that
+/// meet at less than the crease angle.
+fn smooth_normals(mesh: &IndexedMesh, normals: &[Vec3<f32>]) -> Vec<[Vec3<f32>; 3]> {
+    let mut around: Vec<Vec<usize>> = vec![Vec::new(); mesh.vertices.len()];
+    for (i, face) in mesh.faces.iter().enumerate() {
+        for &v in &face.vertices {
+            around[v].push(i);
+        }
+    }
+    mesh.faces
+        .iter()
+        .enumerate()
+        .map(|(i, face)| {
+            face.vertices.map(|v| {
+                around[v]
+                    .iter()
+                    .map(|&j| normals[j])
+                    .filter(|n| n.dot(normals[i]) >= CREASE_COS)
+                    .sum::<Vec3<f32>>()
+                    .try_normalize()
+                    .unwrap_or(normals[i])
+            })
+        })
+        .collect()
+}
+
+/// The part of a camera space polygon with per vertex brightness in front of the near plane.
+fn clip_polygon_near(polygon: &[(Vec3<f32>, f32)], near: f32) -> Vec<(Vec3<f32>, f32)> {
+    let distance = |p: &Vec3<f32>| -p.z() - near;
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (k, &(p, bp)) in polygon.iter().enumerate() {
+        let (q, bq) = polygon[(k + 1) % polygon.len()];
+        let (dp, dq) = (distance(&p), distance(&q));
+        if dp >= 0.0 {
+            clipped.push((p, bp));
+        }
+        if (dp >= 0.0) != (dq >= 0.0) {
+            let t = dp / (dp - dq);
+            let mut cut = p.lerp(q, t);
+            cut[2] = -near;
+            clipped.push((cut, bp + (bq - bp) * t));
+        }
+    }
+    clipped
+}
@@ -0,0 +1,1149 @@
+//! Scenes, either built in or described in a text file.
+//!
+//! Scene files use a subset of [RON](https://github.com/ron-rs/ron): numbers, strings, `true`
+//! and `false`, tuples `(1, 2, 3)`, structs `Name(field: value)` whose name is optional, bare
+//! variants like `Ball`, lists `[a, b]`, maps `{"key": value}` and `//` or `/* */` comments.
+//! Lengths are in meters, angles in degrees and angular velocities in degrees per second.
+//!
+//! ```text
+//! Scene(
+//!     gravity: (0, -9.81, 0),
+//!     solver: (velocity_iterations: 20),
+//!     materials: {
+//!         "rubber": (friction: 0.9, restitution: 0.6, restitution_combine: Max),
+//!     },
+//!     bodies: [
+//!         (name: "ground", shape: Plane(normal: (0, 1, 0)), static: true),
+//!         (
+//!             name: "ball",
+//!             shape: Sphere(radius: 0.25),
+//!             density: 500,
+//!             position: (0, 2, 0),
+//!             velocity: (1, 0, 0),
+//!             material: "rubber",
+//!         ),
+//!         (
+//!             name: "door",
+//!             mesh: "meshes/door.stl",
+//!             collider: Decomposed,
+//!             orientation: (axis: (0, 1, 0), angle: 90),
+//!         ),
+//!     ],
+//!     joints: [
+//!         (kind: Hinge(limits: (-90, 90)), a: "door", anchor: (0, 1, 0), axis: (0, 1, 0)),
+//!     ],
+//! )
+//! ```
+//!
+//! Every field is optional except the shape or mesh of a body and the kind and first body of a
+//! joint. Bodies:
+//!
+//! - `shape`: `Sphere(radius)`, `Box(half_extents)`, `Capsule(half_height, radius)`,
+//!   `Cylinder(half_height, radius)` or `Plane(normal)`, see [Shape].
+//! - `mesh`: path of an STL file, relative to the scene file.
+//! - `collider`: for meshes, `Convex` for the convex hull, `Decomposed` for a
+//!   [convex decomposition](crate::stl::IndexedMesh::convex_decomposition) or `TriMesh` for the
+//!   exact triangles of a static body.
+//! - `static`, `density` (1000 by default), `position`, `orientation` as `(axis, angle)`,
+//!   `velocity`, `angular_velocity`, `ccd` and `material`, either inline or the name of an entry
+//!   of `materials`. The position moves the origin of the shape or mesh file.
+//!
+//! Joints connect the bodies named `a` and `b`, or `a` and the world without `b`. Their `kind`
+//! is `Ball`, `Fixed`, `Hinge(limits: (lower, upper), motor: (target_velocity, max_torque))`,
+//! `Slider(limits)` or `Distance(min, max)`. The joint frame sits at the world space `anchor`,
+//! the position of `a` by default, with its X axis along `axis`. `anchor_b` gives B its own
+//! anchor, for example for distance joints, and `collide_connected` lets the two bodies collide.
+//! The world settings `gravity`, `timestep`, `max_substeps`, `contact_margin`, `ccd_threshold`
+//! and `max_ccd_substeps` as well as the `solver` and `sleep` settings use the field names of
+//! [World], [SolverParams] and [SleepParams].
+
+use crate::body::RigidBody;
+use crate::collider::{Collider, Compound};
+use crate::decomposition::DecompositionParams;
+use crate::island::SleepParams;
+use crate::joint::{Joint, JointKind, Motor};
+use crate::material::{CombineRule, Material};
+use crate::math::{Quat, Transform, Vec3};
+use crate::shape::Shape;
+use crate::solver::{PositionCorrection, SolverParams};
+use crate::stl::read_stl;
+use crate::world::{BodyHandle, World};
+use gxhash::{HashMap, HashMapExt};
+use std::fmt;
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Names accepted by [builtin].
+pub const BUILTIN_SCENES: [&str; 2] = ["demo", "stack"];
+
+/// Density of bodies that do not set one, that of water.
+const DEFAULT_DENSITY: f32 = 1000.0;
+
+/// Builds the scene called `name`, or returns None if there is no such scene.
+///
+/// ```
+/// use rigid_body_physics_engine::scene::{builtin, BUILTIN_SCENES};
+/// for name in BUILTIN_SCENES {
+///     let mut world = builtin(name).unwrap();
+///     world.step(world.timestep);
+///     assert!(world.iter().all(|(_, body)| body.position.is_finite()));
+/// }
+/// assert!(builtin("nothing").is_none());
+/// ```
+pub fn builtin(name: &str) -> Option<World> {
+    match name {
+        "demo" => Some(demo()),
+        "stack" => Some(stack()),
+        _ => None,
+    }
+}
+
+/// Builds the built in scene called `name`, or loads the scene file at path `name`.
+pub fn open(name: &str) -> std::io::Result<World> {
+    match builtin(name) {
+        Some(world) => Ok(world),
+        None => load(name),
+    }
+}
+
+/// Loads a scene file. Errors in the file are [SceneError]s inside the returned error.
+///
+/// ```
+/// use rigid_body_physics_engine::scene;
+/// let mut world = scene::load("scenes/playground.ron").unwrap();
+/// assert!(!world.bodies().is_empty());
+/// world.step(world.timestep);
+/// assert!(world.iter().all(|(_, body)| body.position.is_finite()));
+/// ```
+pub fn load(path: impl AsRef<Path>) -> std::io::Result<World> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    parse(&text, base).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Builds the scene described by `text`, resolving mesh paths relative to `base`.
+///
+/// ```
+/// use rigid_body_physics_engine::scene::parse;
+/// use std::path::Path;
+/// let world = parse(
+///     r#"Scene(
+///         gravity: (0, -1.62, 0),
+///         bodies: [
+///             (shape: Plane(normal: (0, 1, 0)), static: true),
+///             (name: "bob", shape: Sphere(radius: 0.1), position: (1, 2, 0)),
+///         ],
+///         joints: [(kind: Ball, a: "bob", anchor: (0, 2, 0))],
+///     )"#,
+///     Path::new(""),
+/// )
+/// .unwrap();
+/// assert_eq!(world.bodies().len(), 2);
+/// assert_eq!(world.joints().len(), 1);
+/// assert_eq!(world.gravity.y(), -1.62);
+///
+/// let error = parse("Scene(\n    bodies: [(shape: Sphere(radius: -1))],\n)", Path::new(""));
+/// let error = error.err().unwrap();
+/// assert_eq!((error.line, error.column), (2, 37));
+/// assert_eq!(error.message, "radius must be positive");
+/// ```
+pub fn parse(text: &str, base: &Path) -> Result<World> {
+    let mut parser = Parser::new(text);
+    let root = parser.value()?;
+    parser.skip_trivia()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("expected the end of the file after the scene"));
+    }
+    build(&root, base)
+}
+
+/// Mistake in a scene file, at a line and column counted from one.
+///
+/// ```
+/// use rigid_body_physics_engine::scene::parse;
+/// use std::path::Path;
+/// for (text, line, column, message) in [
+///     (
+///         r#"Scene(bodies: [(shape: Sphere(radius: 1), colour: 1)])"#,
+///         1,
+///         43,
+///         "unknown field `colour`",
+///     ),
+///     (
+///         r#"Scene(bodies: [
+///             (name: "a", shape: Sphere(radius: 1)),
+///             (name: "a", shape: Sphere(radius: 1)),
+///         ])"#,
+///         3,
+///         20,
+///         "duplicate body name `a`",
+///     ),
+///     (r#"Scene(joints: [(kind: Ball, a: "nobody")])"#, 1, 32, "unknown body `nobody`"),
+///     (r#"Scene(bodies: [(mesh: "missing.stl")])"#, 1, 23, "unable to open missing.stl"),
+///     (
+///         r#"Scene(bodies: [(shape: Sphere(radius: 1), material: "steel")])"#,
+///         1,
+///         53,
+///         "unknown material `steel`",
+///     ),
+///     ("Scene(gravity: (0, -inf, 0))", 1, 20, "number `-inf` is not finite"),
+///     ("Scene(gravity: (0, 1e39, 0))", 1, 20, "number `1e39` is not finite"),
+///     ("Scene(\n    gravity: (0, -1, 0), /* unterminated\n)", 2, 26, "unterminated comment"),
+/// ] {
+///     let error = parse(text, Path::new("")).err().unwrap();
+///     assert_eq!((error.line, error.column), (line, column), "{text}");
+///     assert!(error.message.starts_with(message), "{text}: {}", error.message);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SceneError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Location {
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    fn error(self, message: impl Into<String>) -> SceneError {
+        SceneError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+/// `name: value` inside a struct.
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    /// Where the name starts.
+    location: Location,
+    value: Node,
+}
+
+/// A value of the scene file together with where it starts.
+#[derive(Clone, Debug)]
+struct Node {
+    location: Location,
+    kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Number(f32),
+    Str(String),
+    Bool(bool),
+    /// A bare name like `Ball`.
+    Unit(String),
+    Tuple(Option<String>, Vec<Node>),
+    Struct(Option<String>, Vec<Field>),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    location: Location,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            index: 0,
+            location: Location { line: 1, column: 1 },
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.location.line += 1;
+            self.location.column = 1;
+        } else {
+            self.location.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        self.location.error(message)
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            match (self.peek(), self.chars.get(self.index + 1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let location = self.location;
+                    self.bump();
+                    self.bump();
+                    while !self.at("*/") {
+                        if self.bump().is_none() {
+                            return Err(location.error("unterminated comment"));
+                        }
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn at(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.index + i) == Some(&c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_trivia()?;
+        match self.peek() {
+            Some(found) if found == c => {
+                self.bump();
+                Ok(())
+            }
+            Some(found) => Err(self.error(format!("expected `{c}`, found `{found}`"))),
+            None => Err(self.error(format!("expected `{c}`, found the end of the file"))),
+        }
+    }
+
+    /// After an element of a sequence: consumes the separating comma and returns false, or
+    /// returns true if the sequence ends with `close`.
+    fn end_of_sequence(&mut self, close: char) -> Result<bool> {
+        self.skip_trivia()?;
+        match self.peek() {
+            Some(',') => {
+                self.bump();
+                self.skip_trivia()?;
+                Ok(self.peek() == Some(close) && self.bump().is_some())
+            }
+            Some(c) if c == close => {
+                self.bump();
+                Ok(true)
+            }
+            Some(found) => Err(self.error(format!("expected `,` or `{close}`, found `{found}`"))),
+            None => Err(self.error(format!("expected `{close}`, found the end of the file"))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Node> {
+        self.skip_trivia()?;
+        let location = self.location;
+        let kind = match self.peek() {
+            None => return Err(self.error("expected a value, found the end of the file")),
+            Some('"') => Kind::Str(self.string()?),
+            Some('[') => {
+                self.bump();
+                let mut items = Vec::new();
+                self.skip_trivia()?;
+                if self.peek() == Some(']') {
+                    self.bump();
+                } else {
+                    loop {
+                        items.push(self.value()?);
+                        if self.end_of_sequence(']')? {
+                            break;
+                        }
+                    }
+                }
+                Kind::List(items)
+            }
+            Some('{') => {
+                self.bump();
+                let mut entries = Vec::new();
+                self.skip_trivia()?;
+                if self.peek() == Some('}') {
+                    self.bump();
+                } else {
+                    loop {
+                        self.skip_trivia()?;
+                        let key_location = self.location;
+                        if self.peek() != Some('"') {
+                            return Err(self.error("expected a string key"));
+                        }
+                        let key = self.string()?;
+                        if entries.iter().any(|(k, _)| *k == key) {
+                            return Err(key_location.error(format!("duplicate key `{key}`")));
+                        }
+                        self.expect(':')?;
+                        entries.push((key, self.value()?));
+                        if self.end_of_sequence('}')? {
+                            break;
+                        }
+                    }
+                }
+                Kind::Map(entries)
+            }
+            Some('(') => self.parenthesized(None)?,
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                Kind::Number(self.number()?)
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.identifier();
+                match name.as_str() {
+                    "true" => Kind::Bool(true),
+                    "false" => Kind::Bool(false),
+                    _ => {
+                        self.skip_trivia()?;
+                        if self.peek() == Some('(') {
+                            self.parenthesized(Some(name))?
+                        } else {
+                            Kind::Unit(name)
+                        }
+                    }
+                }
+            }
+            Some(c) => return Err(self.error(format!("unexpected character `{c}`"))),
+        };
+        Ok(Node { location, kind })
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|&c| c.is_alphanumeric() || c == '_') {
+            name.push(c);
+            self.bump();
+        }
+        name
+    }
+
+    fn number(&mut self) -> Result<f32> {
+        let location = self.location;
+        let mut text = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|&c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            text.push(c);
+            self.bump();
+        }
+        match text.parse::<f32>() {
+            Ok(x) if x.is_finite() => Ok(x),
+            Ok(_) => Err(location.error(format!("number `{text}` is not finite"))),
+            Err(_) => Err(location.error(format!("invalid number `{text}`"))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let location = self.location;
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(location.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c @ ('"' | '\\')) => s.push(c),
+                    _ => return Err(self.error("unknown escape sequence")),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// A tuple or struct after its optional name, starting at the opening parenthesis.
+    fn parenthesized(&mut self, name: Option<String>) -> Result<Kind> {
+        self.expect('(')?;
+        self.skip_trivia()?;
+        if self.peek() == Some(')') {
+            self.bump();
+            return Ok(Kind::Struct(name, Vec::new()));
+        }
+        // It is a struct if it starts with `identifier:`, otherwise go back and read a tuple.
+        let (index, location) = (self.index, self.location);
+        let field = self.identifier();
+        self.skip_trivia()?;
+        let is_struct = !field.is_empty() && self.peek() == Some(':');
+        self.index = index;
+        self.location = location;
+        if !is_struct {
+            let mut items = Vec::new();
+            loop {
+                items.push(self.value()?);
+                if self.end_of_sequence(')')? {
+                    return Ok(Kind::Tuple(name, items));
+                }
+            }
+        }
+        let mut fields: Vec<Field> = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            let location = self.location;
+            let field = self.identifier();
+            if field.is_empty() {
+                return Err(self.error("expected a field name"));
+            }
+            if fields.iter().any(|f| f.name == field) {
+                return Err(location.error(format!("duplicate field `{field}`")));
+            }
+            self.expect(':')?;
+            fields.push(Field {
+                name: field,
+                location,
+                value: self.value()?,
+            });
+            if self.end_of_sequence(')')? {
+                return Ok(Kind::Struct(name, fields));
+            }
+        }
+    }
+}
+
+/// Fields of a struct node, checked against the names a caller understands.
+struct Fields<'a> {
+    location: Location,
+    fields: &'a [Field],
+}
+
+impl<'a> Fields<'a> {
+    fn get(&self, name: &str) -> Option<&'a Node> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| &f.value)
+    }
+
+    fn required(&self, name: &str) -> Result<&'a Node> {
+        self.get(name)
+            .ok_or_else(|| self.location.error(format!("missing field `{name}`")))
+    }
+
+    /// Converts field `name` with `f`, or returns `default` if it is missing.
+    fn or<T>(&self, name: &str, default: T, f: impl FnOnce(&'a Node) -> Result<T>) -> Result<T> {
+        self.get(name).map_or(Ok(default), f)
+    }
+}
+
+impl Node {
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        self.location.error(message)
+    }
+
+    fn number(&self) -> Result<f32> {
+        match self.kind {
+            Kind::Number(x) => Ok(x),
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn positive(&self, name: &str) -> Result<f32> {
+        let x = self.number()?;
+        if x > 0.0 && x.is_finite() {
+            Ok(x)
+        } else {
+            Err(self.error(format!("{name} must be positive")))
+        }
+    }
+
+    fn count(&self) -> Result<usize> {
+        let x = self.number()?;
+        if x >= 0.0 && x.fract() == 0.0 {
+            Ok(x as usize)
+        } else {
+            Err(self.error("expected a whole number"))
+        }
+    }
+
+    fn boolean(&self) -> Result<bool> {
+        match self.kind {
+            Kind::Bool(b) => Ok(b),
+            _ => Err(self.error("expected `true` or `false`")),
+        }
+    }
+
+    fn string(&self) -> Result<&str> {
+        match &self.kind {
+            Kind::Str(s) => Ok(s),
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    fn list(&self) -> Result<&[Node]> {
+        match &self.kind {
+            Kind::List(items) => Ok(items),
+            _ => Err(self.error("expected a list `[...]`")),
+        }
+    }
+
+    fn pair(&self) -> Result<[f32; 2]> {
+        match &self.kind {
+            Kind::Tuple(None, items) if items.len() == 2 => {
+                Ok([items[0].number()?, items[1].number()?])
+            }
+            _ => Err(self.error("expected a pair `(a, b)`")),
+        }
+    }
+
+    fn vec3(&self) -> Result<Vec3<f32>> {
+        match &self.kind {
+            Kind::Tuple(None, items) if items.len() == 3 => Ok(Vec3::new([
+                items[0].number()?,
+                items[1].number()?,
+                items[2].number()?,
+            ])),
+            _ => Err(self.error("expected a vector `(x, y, z)`")),
+        }
+    }
+
+    fn direction(&self) -> Result<Vec3<f32>> {
+        self.vec3()?
+            .try_normalize()
+            .ok_or_else(|| self.error("direction must not be zero"))
+    }
+
+    /// The fields of a struct, which may be named `name`, rejecting fields not in `known`.
+    fn fields(&self, name: Option<&str>, known: &[&str]) -> Result<Fields<'_>> {
+        let fields: &[Field] = match &self.kind {
+            Kind::Struct(found, fields) if found.is_none() || found.as_deref() == name => fields,
+            Kind::Unit(found) if Some(found.as_str()) == name => &[],
+            _ => {
+                return Err(self.error(match name {
+                    Some(name) => format!("expected `{name}(...)`"),
+                    None => "expected a struct `(field: value, ...)`".to_string(),
+                }))
+            }
+        };
+        self.check_fields(fields, known)
+    }
+
+    /// Name and fields of an enum variant, given either as `Name` or as `Name(field: value)`.
+    fn variant(&self) -> Result<(&str, &[Field])> {
+        match &self.kind {
+            Kind::Unit(name) => Ok((name, &[])),
+            Kind::Struct(Some(name), fields) => Ok((name, fields)),
+            _ => Err(self.error("expected a name like `Name` or `Name(field: value)`")),
+        }
+    }
+
+    fn check_fields<'a>(&self, fields: &'a [Field], known: &[&str]) -> Result<Fields<'a>> {
+        if let Some(field) = fields.iter().find(|f| !known.contains(&f.name.as_str())) {
+            let message = match known {
+                [] => format!("unknown field `{}`, expected none", field.name),
+                _ => format!(
+                    "unknown field `{}`, expected one of {}",
+                    field.name,
+                    known.join(", ")
+                ),
+            };
+            return Err(field.location.error(message));
+        }
+        Ok(Fields {
+            location: self.location,
+            fields,
+        })
+    }
+
+    /// One of the unit variants `names`, returned as the index of its name.
+    fn choice(&self, names: &[&str]) -> Result<usize> {
+        match &self.kind {
+            Kind::Unit(name) => names.iter().position(|n| n == name),
+            _ => None,
+        }
+        .ok_or_else(|| self.error(format!("expected one of {}", names.join(", "))))
+    }
+}
+
+fn build(root: &Node, base: &Path) -> Result<World> {
+    let scene = root.fields(
+        Some("Scene"),
+        &[
+            "gravity",
+            "timestep",
+            "max_substeps",
+            "contact_margin",
+            "ccd_threshold",
+            "max_ccd_substeps",
+            "solver",
+            "sleep",
+            "materials",
+            "bodies",
+            "joints",
+        ],
+    )?;
+    let mut world = World::new();
+    world.gravity = scene.or("gravity", world.gravity, Node::vec3)?;
+    world.timestep = scene.or("timestep", world.timestep, |n| n.positive("timestep"))?;
+    world.max_substeps = scene.or("max_substeps", world.max_substeps, Node::count)?;
+    world.contact_margin = scene.or("contact_margin", world.contact_margin, Node::number)?;
+    world.ccd_threshold = scene.or("ccd_threshold", world.ccd_threshold, Node::number)?;
+    world.max_ccd_substeps = scene.or("max_ccd_substeps", world.max_ccd_substeps, Node::count)?;
+    if let Some(node) = scene.get("solver") {
+        world.solver = solver_params(node)?;
+    }
+    if let Some(node) = scene.get("sleep") {
+        world.sleep = sleep_params(node)?;
+    }
+
+    let mut materials = HashMap::new();
+    if let Some(node) = scene.get("materials") {
+        let Kind::Map(entries) = &node.kind else {
+            return Err(node.error("expected a map `{\"name\": (...)}`"));
+        };
+        for (name, node) in entries {
+            materials.insert(name.as_str(), material(node)?);
+        }
+    }
+
+    let mut names = HashMap::new();
+    for node in scene.or("bodies", &[][..], Node::list)? {
+        let (name, body) = body(node, base, &materials)?;
+        let handle = world.add_body(body);
+        if let Some(name) = name {
+            if names.insert(name.string()?, handle).is_some() {
+                return Err(name.error(format!("duplicate body name `{}`", name.string()?)));
+            }
+        }
+    }
+    for node in scene.or("joints", &[][..], Node::list)? {
+        joint(node, &mut world, &names)?;
+    }
+    Ok(world)
+}
+
+fn solver_params(node: &Node) -> Result<SolverParams> {
+    let fields = node.fields(
+        None,
+        &[
+            "velocity_iterations",
+            "position_iterations",
+            "position_correction",
+            "baumgarte",
+            "allowed_penetration",
+            "restitution_threshold",
+            "warm_starting",
+        ],
+    )?;
+    let default = SolverParams::default();
+    Ok(SolverParams {
+        velocity_iterations: fields.or(
+            "velocity_iterations",
+            default.velocity_iterations,
+            Node::count,
+        )?,
+        position_iterations: fields.or(
+            "position_iterations",
+            default.position_iterations,
+            Node::count,
+        )?,
+        position_correction: fields.or(
+            "position_correction",
+            default.position_correction,
+            |n| {
+                Ok(match n.choice(&["Baumgarte", "SplitImpulse"])? {
+                    0 => PositionCorrection::Baumgarte,
+                    _ => PositionCorrection::SplitImpulse,
+                })
+            },
+        )?,
+        baumgarte: fields.or("baumgarte", default.baumgarte, Node::number)?,
+        allowed_penetration: fields.or(
+            "allowed_penetration",
+            default.allowed_penetration,
+            Node::number,
+        )?,
+        restitution_threshold: fields.or(
+            "restitution_threshold",
+            default.restitution_threshold,
+            Node::number,
+        )?,
+        warm_starting: fields.or("warm_starting", default.warm_starting, Node::boolean)?,
+    })
+}
+
+fn sleep_params(node: &Node) -> Result<SleepParams> {
+    let fields = node.fields(
+        None,
+        &[
+            "enabled",
+            "linear_threshold",
+            "angular_threshold",
+            "time_to_sleep",
+        ],
+    )?;
+    let default = SleepParams::default();
+    Ok(SleepParams {
+        enabled: fields.or("enabled", default.enabled, Node::boolean)?,
+        linear_threshold: fields.or("linear_threshold", default.linear_threshold, Node::number)?,
+        angular_threshold: fields.or("angular_threshold", default.angular_threshold, |n| {
+            Ok(n.number()?.to_radians())
+        })?,
+        time_to_sleep: fields.or("time_to_sleep", default.time_to_sleep, Node::number)?,
+    })
+}
+
+fn material(node: &Node) -> Result<Material> {
+    let fields = node.fields(
+        None,
+        &[
+            "friction",
+            "restitution",
+            "friction_combine",
+            "restitution_combine",
+        ],
+    )?;
+    let rule = |n: &Node| {
+        let rules = [
+            CombineRule::Average,
+            CombineRule::Min,
+            CombineRule::Multiply,
+            CombineRule::Max,
+        ];
+        Ok(rules[n.choice(&["Average", "Min", "Multiply", "Max"])?])
+    };
+    let default = Material::default();
+    Ok(Material {
+        friction: fields.or("friction", default.friction, Node::number)?,
+        restitution: fields.or("restitution", default.restitution, Node::number)?,
+        friction_combine: fields.or("friction_combine", default.friction_combine, rule)?,
+        restitution_combine: fields.or("restitution_combine", default.restitution_combine, rule)?,
+    })
+}
+
+fn shape(node: &Node) -> Result<Shape> {
+    let (name, fields) = node.variant()?;
+    let radius = |f: &Fields| f.required("radius")?.positive("radius");
+    let half_height = |f: &Fields| f.required("half_height")?.positive("half_height");
+    Ok(match name {
+        "Sphere" => {
+            let f = node.check_fields(fields, &["radius"])?;
+            Shape::Sphere {
+                radius: radius(&f)?,
+            }
+        }
+        "Box" => {
+            let f = node.check_fields(fields, &["half_extents"])?;
+            let extents = f.required("half_extents")?;
+            let half_extents = extents.vec3()?;
+            if !(0..3).all(|i| half_extents[i] > 0.0) {
+                return Err(extents.error("half_extents must be positive"));
+            }
+            Shape::Box { half_extents }
+        }
+        "Capsule" | "Cylinder" => {
+            let f = node.check_fields(fields, &["half_height", "radius"])?;
+            let (half_height, radius) = (half_height(&f)?, radius(&f)?);
+            if name == "Capsule" {
+                Shape::Capsule {
+                    half_height,
+                    radius,
+                }
+            } else {
+                Shape::Cylinder {
+                    half_height,
+                    radius,
+                }
+            }
+        }
+        "Plane" => {
+            let f = node.check_fields(fields, &["normal"])?;
+            Shape::Plane {
+                normal: f.required("normal")?.direction()?,
+            }
+        }
+        _ => {
+            return Err(node.error(format!(
+                "unknown shape `{name}`, expected one of Sphere, Box, Capsule, Cylinder, Plane"
+            )))
+        }
+    })
+}
+
+fn orientation(node: &Node) -> Result<Quat<f32>> {
+    let fields = node.fields(None, &["axis", "angle"])?;
+    let axis = fields.required("axis")?.direction()?;
+    let angle = fields.required("angle")?.number()?;
+    Ok(Quat::from_axis_angle(axis, angle.to_radians()))
+}
+
+fn body<'a>(
+    node: &'a Node,
+    base: &Path,
+    materials: &HashMap<&str, Material>,
+) -> Result<(Option<&'a Node>, RigidBody)> {
+    let fields = node.fields(
+        None,
+        &[
+            "name",
+            "shape",
+            "mesh",
+            "collider",
+            "static",
+            "density",
+            "position",
+            "orientation",
+            "velocity",
+            "angular_velocity",
+            "material",
+            "ccd",
+        ],
+    )?;
+    let name = fields.get("name");
+    if let Some(name) = name {
+        name.string()?;
+    }
+    let is_static = fields.or("static", false, Node::boolean)?;
+    let density = fields.or("density", DEFAULT_DENSITY, |n| n.positive("density"))?;
+    let invalid = |node: &Node, e: Error| node.error(e.to_string());
+    let mut body = match (fields.get("shape"), fields.get("mesh")) {
+        (Some(_), Some(mesh)) => return Err(mesh.error("a body has either a shape or a mesh")),
+        (None, None) => return Err(node.error("a body needs a `shape` or a `mesh`")),
+        (Some(node), None) => {
+            if fields.get("collider").is_some() {
+                return Err(node.error("`collider` is only for meshes"));
+            }
+            let shape = shape(node)?;
+            if is_static {
+                RigidBody::new_static_shape(shape)
+            } else {
+                RigidBody::from_shape(shape, density).map_err(|e| invalid(node, e))?
+            }
+        }
+        (None, Some(node)) => {
+            let path = base.join(node.string()?);
+            let file = std::fs::File::open(&path)
+                .map_err(|e| node.error(format!("unable to open {}: {e}", path.display())))?;
+            let mesh = read_stl(&mut BufReader::new(file))
+                .map_err(|e| node.error(format!("unable to read {}: {e}", path.display())))?;
+            let collider = fields.or("collider", 0, |n| {
+                n.choice(&["Convex", "Decomposed", "TriMesh"])
+            })?;
+            let mut body = match (collider, is_static) {
+                (2, false) => {
+                    let node = fields.required("collider")?;
+                    return Err(node.error("only static bodies can use a TriMesh collider"));
+                }
+                (2, true) => RigidBody::new_trimesh(Arc::new(mesh)),
+                (_, true) => RigidBody::new_static(Arc::new(mesh)),
+                (_, false) => RigidBody::from_mesh(mesh, density).map_err(|e| invalid(node, e))?,
+            };
+            if collider == 1 {
+                let pieces = body
+                    .mesh
+                    .convex_decomposition(&DecompositionParams::default())
+                    .map_err(|e| invalid(node, e))?;
+                body.collider = Collider::Compound(Arc::new(Compound::new(pieces)));
+            }
+            body
+        }
+    };
+    if let Some(node) = fields.get("material") {
+        body.material = match &node.kind {
+            Kind::Str(name) => *materials
+                .get(name.as_str())
+                .ok_or_else(|| node.error(format!("unknown material `{name}`")))?,
+            _ => material(node)?,
+        };
+    }
+    let rotation = fields.or("orientation", Quat::IDENTITY, orientation)?;
+    // Bodies built from meshes start at their center of mass, which moves along.
+    let position = fields.or("position", Vec3::ZERO, Node::vec3)?;
+    body.position = position + rotation.rotate(body.position);
+    body.orientation = rotation;
+    if !is_static {
+        body.linear_velocity = fields.or("velocity", Vec3::ZERO, Node::vec3)?;
+        body.angular_velocity =
+            fields.or("angular_velocity", Vec3::ZERO, Node::vec3)? * 1f32.to_radians();
+        body.ccd = fields.or("ccd", false, Node::boolean)?;
+    }
+    Ok((name, body))
+}
+
+fn joint_kind(node: &Node) -> Result<JointKind> {
+    let (name, fields) = node.variant()?;
+    let limits = |f: &Fields, scale: f32| {
+        f.get("limits")
+            .map(|n| {
+                let [lower, upper] = n.pair()?;
+                if lower > upper {
+                    return Err(n.error("the lower limit is above the upper limit"));
+                }
+                Ok([lower * scale, upper * scale])
+            })
+            .transpose()
+    };
+    Ok(match name {
+        "Ball" | "Fixed" => {
+            node.check_fields(fields, &[])?;
+            if name == "Ball" {
+                JointKind::Ball
+            } else {
+                JointKind::Fixed
+            }
+        }
+        "Hinge" => {
+            let f = node.check_fields(fields, &["limits", "motor"])?;
+            let motor = f
+                .get("motor")
+                .map(|n| {
+                    let [target_velocity, max_torque] = n.pair()?;
+                    Ok(Motor {
+                        target_velocity: target_velocity.to_radians(),
+                        max_torque,
+                    })
+                })
+                .transpose()?;
+            JointKind::Hinge {
+                limits: limits(&f, 1f32.to_radians())?,
+                motor,
+            }
+        }
+        "Slider" => {
+            let f = node.check_fields(fields, &["limits"])?;
+            JointKind::Slider {
+                limits: limits(&f, 1.0)?,
+            }
+        }
+        "Distance" => {
+            let f = node.check_fields(fields, &["min", "max"])?;
+            let min = f.required("min")?.number()?;
+            let max = f.required("max")?;
+            if max.number()? < min {
+                return Err(max.error("max must not be below min"));
+            }
+            JointKind::Distance {
+                min,
+                max: max.number()?,
+            }
+        }
+        _ => {
+            return Err(node.error(format!(
+                "unknown joint `{name}`, expected one of Ball, Fixed, Hinge, Slider, Distance"
+            )))
+        }
+    })
+}
+
+fn joint(node: &Node, world: &mut World, names: &HashMap<&str, BodyHandle>) -> Result<()> {
+    let fields = node.fields(
+        None,
+        &[
+            "kind",
+            "a",
+            "b",
+            "anchor",
+            "anchor_b",
+            "axis",
+            "collide_connected",
+        ],
+    )?;
+    let kind = joint_kind(fields.required("kind")?)?;
+    let lookup = |node: &Node| {
+        let name = node.string()?;
+        names
+            .get(name)
+            .copied()
+            .ok_or_else(|| node.error(format!("unknown body `{name}`")))
+    };
+    let a = lookup(fields.required("a")?)?;
+    let b = fields.get("b").map(lookup).transpose()?;
+    let anchor = fields.or("anchor", world.body(a).position, Node::vec3)?;
+    let axis = fields.or("axis", Vec3::X, Node::direction)?;
+    let rotation = Quat::from_rotation_arc(Vec3::X, axis);
+    let handle = match fields.get("anchor_b") {
+        None => world.add_joint(kind, a, b, Transform::new(anchor, rotation)),
+        Some(node) => {
+            let frame_b = Transform::new(node.vec3()?, rotation);
+            let frame_a = world.body(a).transform().inverse() * Transform::new(anchor, rotation);
+            let frame_b = match b {
+                Some(b) => world.body(b).transform().inverse() * frame_b,
+                None => frame_b,
+            };
+            world.insert_joint(Joint::new(kind, a, frame_a, b, frame_b))
+        }
+    };
+    world.joint_mut(handle).collide_connected =
+        fields.or("collide_connected", false, Node::boolean)?;
+    Ok(())
+}
+
+/// Static box with its top face at height zero.
+fn floor(half_width: f32) -> RigidBody {
+    let mut ground = RigidBody::new_static_shape(Shape::Box {
+        half_extents: Vec3::new([half_width, 0.5, half_width]),
+    });
+    ground.position = Vec3::new([0.0, -0.5, 0.0]);
+    ground
+}
+
+/// A floor with a few primitives falling onto it.
+fn demo() -> World {
+    let mut world = World::new();
+    world.add_body(floor(8.0));
+    let shapes = [
+        Shape::Box {
+            half_extents: Vec3::new([0.5, 0.4, 0.3]),
+        },
+        Shape::Sphere { radius: 0.5 },
+        Shape::Capsule {
+            half_height: 0.4,
+            radius: 0.3,
+        },
+        Shape::Cylinder {
+            half_height: 0.4,
+            radius: 0.4,
+        },
+    ];
+    for (i, shape) in shapes.into_iter().cycle().take(12).enumerate() {
+        let mut body = RigidBody::from_shape(shape, 1000.0).unwrap();
+        let angle = i as f32 * 0.9;
+        body.position = Vec3::new([angle.cos() * 2.0, 2.0 + i as f32 * 1.2, angle.sin() * 2.0]);
+        body.orientation = Quat::from_axis_angle(Vec3::new([1.0, 0.5, 0.2]).normalize(), angle);
+        world.add_body(body);
+    }
+    world
+}
+
+/// A tower of five cubes resting on a floor.
+fn stack() -> World {
+    let mut world = World::new();
+    world.add_body(floor(4.0));
+    let cube = Shape::Box {
+        half_extents: Vec3::splat(0.5),
+    };
+    for i in 0..5 {
+        let mut body = RigidBody::from_shape(cube, 1000.0).unwrap();
+        body.position = Vec3::new([0.0, 0.5 + i as f32, 0.0]);
+        world.add_body(body);
+    }
+    world
+}
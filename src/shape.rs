@@ -0,0 +1,385 @@
+use crate::broadphase::Aabb;
+use crate::mass::MassProperties;
+use crate::math::{Mat3, Vec3};
+use crate::narrowphase::SupportMap;
+use crate::stl::{IndexedMesh, IndexedTriangle};
+use std::f32::consts::PI;
+use std::io::Result;
+
+/// Side length of the square a [Shape::Plane] turns into in [Shape::to_mesh].
+pub const PLANE_MESH_SIZE: f32 = 100.0;
+/// How far the bounding box of a plane reaches, planes are unbounded but boxes are not.
+const PLANE_EXTENT: f32 = 1e5;
+/// Points sampled around each rim of a cylinder for [SupportMap::support_points].
+const RIM_POINTS: usize = 8;
+
+/// Collision shape with an analytic description, centered on the body origin.
+///
+/// Capsules and cylinders stand along the Y axis.
+///
+/// ```
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::shape::Shape;
+/// use rigid_body_physics_engine::stl;
+/// let brick = Shape::Box { half_extents: Vec3::new([1.0, 0.5, 0.25]) };
+/// let props = brick.mass_properties(2.0).unwrap();
+/// assert_eq!(props.mass, 2.0);
+/// let mesh = brick.to_mesh(16);
+/// mesh.validate().unwrap();
+/// let mut binary_stl = Vec::<u8>::new();
+/// stl::write_stl(&mut binary_stl, mesh.triangles()).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vec3<f32>,
+    },
+    /// Cylinder with hemispherical caps, `half_height` is half the length of the cylinder part.
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    Cylinder {
+        half_height: f32,
+        radius: f32,
+    },
+    /// Half space below the plane through the origin with unit `normal`. Only for static bodies.
+    Plane {
+        normal: Vec3<f32>,
+    },
+}
+
+impl Shape {
+    /// Volume, mass and inertia of the shape filled with `density`.
+    ///
+    /// Planes are unbounded and give an error.
+    pub fn mass_properties(&self, density: f32) -> Result<MassProperties> {
+        let (volume, moments) = match *self {
+            Shape::Sphere { radius } => {
+                let volume = 4.0 / 3.0 * PI * radius.powi(3);
+                (volume, Vec3::splat(0.4 * volume * radius * radius))
+            }
+            Shape::Box { half_extents } => {
+                let volume = 8.0 * half_extents.x() * half_extents.y() * half_extents.z();
+                let [x, y, z] = <[f32; 3]>::from(half_extents.mul_elem(half_extents));
+                (volume, Vec3::new([y + z, x + z, x + y]) * (volume / 3.0))
+            }
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => {
+                let (h, r) = (2.0 * half_height, radius);
+                let cylinder = PI * r * r * h;
+                let caps = 4.0 / 3.0 * PI * r.powi(3);
+                let axial = cylinder * r * r / 2.0 + caps * 0.4 * r * r;
+                // Each cap is a half sphere whose center of mass sits 3r/8 from the flat side.
+                let radial = cylinder * (h * h / 12.0 + r * r / 4.0)
+                    + caps * (0.4 * r * r + h * h / 4.0 + 3.0 * h * r / 8.0);
+                (cylinder + caps, Vec3::new([radial, axial, radial]))
+            }
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => {
+                let (h, r) = (2.0 * half_height, radius);
+                let volume = PI * r * r * h;
+                let radial = volume * (3.0 * r * r + h * h) / 12.0;
+                (volume, Vec3::new([radial, volume * r * r / 2.0, radial]))
+            }
+            Shape::Plane { .. } => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "a plane has no finite volume",
+                ))
+            }
+        };
+        if !(volume.is_finite() && volume > 0.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("shape {:?} has no volume", self),
+            ));
+        }
+        let moments = moments * density;
+        // Principal moments in ascending order with the matching axes as columns.
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| moments[i].total_cmp(&moments[j]));
+        let mut axes = order.map(|i| {
+            let mut v = Vec3::ZERO;
+            v[i] = 1.0;
+            v
+        });
+        // Keep the axes a rotation rather than a reflection.
+        if axes[0].cross(axes[1]).dot(axes[2]) < 0.0 {
+            axes[2] = -axes[2];
+        }
+        Ok(MassProperties {
+            volume,
+            mass: volume * density,
+            center_of_mass: Vec3::ZERO,
+            inertia: Mat3::from_diagonal(moments),
+            principal_moments: Vec3::new(order.map(|i| moments[i])),
+            principal_axes: Mat3::from_cols(axes),
+        })
+    }
+
+    /// Bounding box in body space.
+    pub fn aabb(&self) -> Aabb {
+        let half = match *self {
+            Shape::Sphere { radius } => Vec3::splat(radius),
+            Shape::Box { half_extents } => half_extents,
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => Vec3::new([radius, half_height + radius, radius]),
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => Vec3::new([radius, half_height, radius]),
+            Shape::Plane { normal } => {
+                // Axis aligned planes get a box that ends at the plane, others a cube around it.
+                let mut aabb = Aabb::new(Vec3::splat(-PLANE_EXTENT), Vec3::splat(PLANE_EXTENT));
+                let axis = normal.abs().max_axis();
+                if normal.abs()[axis] > 1.0 - 1e-6 {
+                    if normal[axis] > 0.0 {
+                        aabb.max[axis] = 0.0;
+                    } else {
+                        aabb.min[axis] = 0.0;
+                    }
+                }
+                return aabb;
+            }
+        };
+        Aabb::new(-half, half)
+    }
+
+    /// Distance of the farthest point from the body origin, infinite for planes.
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            Shape::Sphere { radius } => radius,
+            Shape::Box { half_extents } => half_extents.length(),
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => half_height + radius,
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => half_height.hypot(radius),
+            Shape::Plane { .. } => f32::INFINITY,
+        }
+    }
+
+    /// Closed triangle mesh of the shape, with `segments` vertices around every curved
+    /// circumference. Planes become a square of [PLANE_MESH_SIZE].
+    pub fn to_mesh(&self, segments: usize) -> IndexedMesh {
+        let segments = segments.max(3);
+        // Latitude steps of a quarter circle.
+        let quarter = (segments / 4).max(1);
+        let arc = |center: f32, radius: f32, from: usize, to: usize| {
+            (from..=to).map(move |i| {
+                if i == 0 || i == 2 * quarter {
+                    return (0.0, center + radius * (i as f32 / quarter as f32 - 1.0));
+                }
+                let angle = PI / 2.0 * (i as f32 / quarter as f32 - 1.0);
+                (radius * angle.cos(), center + radius * angle.sin())
+            })
+        };
+        let profile: Vec<(f32, f32)> = match *self {
+            Shape::Sphere { radius } => arc(0.0, radius, 0, 2 * quarter).collect(),
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => arc(-half_height, radius, 0, quarter)
+                .chain(arc(half_height, radius, quarter, 2 * quarter))
+                .collect(),
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => vec![
+                (0.0, -half_height),
+                (radius, -half_height),
+                (radius, half_height),
+                (0.0, half_height),
+            ],
+            Shape::Box { half_extents } => return IndexedMesh::cuboid(half_extents),
+            Shape::Plane { normal } => return plane_mesh(normal),
+        };
+        lathe(&profile, segments)
+    }
+}
+
+/// Corners of a box, vertex `i` is on the positive side of axis `k` if bit `k` of `i` is set.
+fn box_corners(half_extents: Vec3<f32>) -> [Vec3<f32>; 8] {
+    std::array::from_fn(|i| {
+        let corner = Vec3::new([i & 1, (i >> 1) & 1, (i >> 2) & 1].map(|c| c as f32 * 2.0 - 1.0));
+        corner.mul_elem(half_extents)
+    })
+}
+
+impl IndexedMesh {
+    /// Closed box centered on the origin, the mesh of a [Shape::Box].
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::math::Vec3;
+    /// use rigid_body_physics_engine::stl::IndexedMesh;
+    /// let brick = IndexedMesh::cuboid(Vec3::new([1.0, 0.5, 0.25]));
+    /// assert!(brick.validate().is_ok());
+    /// assert_eq!(brick.mass_properties(1.0).unwrap().volume, 1.0);
+    /// ```
+    pub fn cuboid(half_extents: Vec3<f32>) -> Self {
+        let vertices = box_corners(half_extents).to_vec();
+        // Two triangles per side, wound counter clockwise seen from outside.
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let faces = quads
+            .into_iter()
+            .flat_map(|[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .map(|vertices| IndexedTriangle {
+                normal: Vec3::ZERO,
+                vertices,
+            })
+            .collect();
+        with_normals(IndexedMesh { vertices, faces })
+    }
+}
+
+fn plane_mesh(normal: Vec3<f32>) -> IndexedMesh {
+    let u = normal.any_orthonormal() * (PLANE_MESH_SIZE / 2.0);
+    let v = normal.cross(u);
+    IndexedMesh {
+        vertices: vec![-u - v, u - v, u + v, v - u],
+        faces: [[0, 1, 2], [0, 2, 3]]
+            .map(|vertices| IndexedTriangle { normal, vertices })
+            .to_vec(),
+    }
+}
+
+/// Surface of revolution around the Y axis of a profile of `(radius, y)` points from bottom to
+/// top. Points with zero radius become a single pole vertex.
+fn lathe(profile: &[(f32, f32)], segments: usize) -> IndexedMesh {
+    let mut vertices = Vec::new();
+    // First vertex of every ring and whether it is a pole.
+    let mut rings = Vec::with_capacity(profile.len());
+    for &(radius, y) in profile {
+        let pole = radius == 0.0;
+        rings.push((vertices.len(), pole));
+        if pole {
+            vertices.push(Vec3::new([0.0, y, 0.0]));
+            continue;
+        }
+        for j in 0..segments {
+            let angle = 2.0 * PI * j as f32 / segments as f32;
+            vertices.push(Vec3::new([radius * angle.cos(), y, -radius * angle.sin()]));
+        }
+    }
+    let index = |(start, pole): (usize, bool), j: usize| {
+        if pole {
+            start
+        } else {
+            start + j % segments
+        }
+    };
+    let mut faces = Vec::new();
+    for pair in rings.windows(2) {
+        let (lower, upper) = (pair[0], pair[1]);
+        for j in 0..segments {
+            if !lower.1 {
+                faces.push([index(lower, j), index(lower, j + 1), index(upper, j)]);
+            }
+            if !upper.1 {
+                faces.push([index(lower, j + 1), index(upper, j + 1), index(upper, j)]);
+            }
+        }
+    }
+    let faces = faces
+        .into_iter()
+        .map(|vertices| IndexedTriangle {
+            normal: Vec3::ZERO,
+            vertices,
+        })
+        .collect();
+    with_normals(IndexedMesh { vertices, faces })
+}
+
+/// Fills in the face normals from the winding.
+fn with_normals(mut mesh: IndexedMesh) -> IndexedMesh {
+    for face in &mut mesh.faces {
+        let [a, b, c] = face.vertices.map(|i| mesh.vertices[i]);
+        face.normal = (b - a).cross(c - a).try_normalize().unwrap_or(Vec3::ZERO);
+    }
+    mesh
+}
+
+impl SupportMap for Shape {
+    /// Planes are unbounded, their support point is the origin.
+    fn support(&self, dir: Vec3<f32>) -> Vec3<f32> {
+        let unit = dir.try_normalize().unwrap_or(Vec3::new([0.0, 1.0, 0.0]));
+        let sign = |v: f32| if v < 0.0 { -1.0 } else { 1.0 };
+        match *self {
+            Shape::Sphere { radius } => unit * radius,
+            Shape::Box { half_extents } => {
+                Vec3::new([0, 1, 2].map(|i| sign(dir[i]) * half_extents[i]))
+            }
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => Vec3::new([0.0, sign(dir.y()) * half_height, 0.0]) + unit * radius,
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => {
+                let radial = Vec3::new([dir.x(), 0.0, dir.z()])
+                    .try_normalize()
+                    .unwrap_or(Vec3::ZERO);
+                radial * radius + Vec3::new([0.0, sign(dir.y()) * half_height, 0.0])
+            }
+            Shape::Plane { .. } => Vec3::ZERO,
+        }
+    }
+
+    fn support_points(&self, dir: Vec3<f32>, tolerance: f32) -> Vec<Vec3<f32>> {
+        let candidates: Vec<Vec3<f32>> = match *self {
+            Shape::Box { half_extents } => box_corners(half_extents).to_vec(),
+            Shape::Capsule {
+                half_height,
+                radius,
+            } => {
+                let unit = dir.try_normalize().unwrap_or(Vec3::new([0.0, 1.0, 0.0]));
+                [-half_height, half_height]
+                    .map(|y| Vec3::new([0.0, y, 0.0]) + unit * radius)
+                    .to_vec()
+            }
+            Shape::Cylinder {
+                half_height,
+                radius,
+            } => {
+                let exact = [-1.0, 1.0].map(|s| {
+                    let mut p = self.support(Vec3::new([dir.x(), 0.0, dir.z()]));
+                    p[1] = s * half_height;
+                    p
+                });
+                let rim = (0..RIM_POINTS).flat_map(|j| {
+                    let angle = 2.0 * PI * j as f32 / RIM_POINTS as f32;
+                    let (sin, cos) = angle.sin_cos();
+                    [-half_height, half_height].map(|y| Vec3::new([radius * cos, y, radius * sin]))
+                });
+                exact.into_iter().chain(rim).collect()
+            }
+            Shape::Sphere { .. } | Shape::Plane { .. } => return vec![self.support(dir)],
+        };
+        let max = self.support(dir).dot(dir);
+        candidates
+            .into_iter()
+            .filter(|v| v.dot(dir) >= max - tolerance)
+            .collect()
+    }
+}
@@ -0,0 +1,422 @@
+use crate::body::RigidBody;
+use crate::integrator::angular_velocity_step;
+use crate::joint::{Joint, JointConstraint};
+use crate::math::{Mat3, Vec3};
+use crate::narrowphase::Contact;
+use gxhash::{HashMap, HashMapExt};
+
+/// How the solver removes penetration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionCorrection {
+    /// Feeds a fraction of the penetration back into the velocity constraints. Cheap, but the
+    /// extra velocity stays in the bodies and shows up as small bounces.
+    Baumgarte,
+    /// Pushes the bodies apart with separate pseudo velocities that move them once and are then
+    /// thrown away, so correcting penetration adds no energy.
+    SplitImpulse,
+}
+
+/// Settings of the [ContactSolver].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverParams {
+    /// Gauss-Seidel passes over all contacts for the velocities.
+    pub velocity_iterations: usize,
+    /// Passes over all contacts for the pseudo velocities of [PositionCorrection::SplitImpulse].
+    pub position_iterations: usize,
+    pub position_correction: PositionCorrection,
+    /// Fraction of the penetration removed per step.
+    pub baumgarte: f32,
+    /// Penetration that is left alone, so resting contacts stay touching instead of jittering.
+    pub allowed_penetration: f32,
+    /// Bodies approaching slower than this do not bounce, which lets them come to rest.
+    pub restitution_threshold: f32,
+    /// Start every step from the impulses of the last one, which makes stacks converge much
+    /// faster.
+    pub warm_starting: bool,
+}
+
+impl Default for SolverParams {
+    fn default() -> Self {
+        Self {
+            velocity_iterations: 10,
+            position_iterations: 4,
+            position_correction: PositionCorrection::SplitImpulse,
+            baumgarte: 0.2,
+            allowed_penetration: 0.005,
+            restitution_threshold: 1.0,
+            warm_starting: true,
+        }
+    }
+}
+
+/// Contact points of two steps closer than this in both bodies are the same point for warm
+/// starting.
+const WARM_START_DISTANCE: f32 = 0.05;
+
+/// Velocities of a body while the solver runs.
+#[derive(Clone)]
+pub(crate) struct SolverBody {
+    pub(crate) linear: Vec3<f32>,
+    pub(crate) angular: Vec3<f32>,
+    pseudo_linear: Vec3<f32>,
+    pseudo_angular: Vec3<f32>,
+    pub(crate) inv_mass: f32,
+    pub(crate) inv_inertia: Mat3<f32>,
+}
+
+impl SolverBody {
+    fn fixed() -> Self {
+        Self {
+            linear: Vec3::ZERO,
+            angular: Vec3::ZERO,
+            pseudo_linear: Vec3::ZERO,
+            pseudo_angular: Vec3::ZERO,
+            inv_mass: 0.0,
+            inv_inertia: Mat3::ZERO,
+        }
+    }
+
+    fn velocity_at(&self, r: Vec3<f32>) -> Vec3<f32> {
+        self.linear + self.angular.cross(r)
+    }
+
+    fn pseudo_velocity_at(&self, r: Vec3<f32>) -> Vec3<f32> {
+        self.pseudo_linear + self.pseudo_angular.cross(r)
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec3<f32>, r: Vec3<f32>) {
+        self.linear += impulse * self.inv_mass;
+        self.angular += self.inv_inertia * r.cross(impulse);
+    }
+
+    fn apply_pseudo_impulse(&mut self, impulse: Vec3<f32>, r: Vec3<f32>) {
+        self.pseudo_linear += impulse * self.inv_mass;
+        self.pseudo_angular += self.inv_inertia * r.cross(impulse);
+    }
+
+    /// Inverse of the effective mass along `dir` at offset `r`.
+    fn inv_effective_mass(&self, r: Vec3<f32>, dir: Vec3<f32>) -> f32 {
+        let rn = r.cross(dir);
+        self.inv_mass + rn.dot(self.inv_inertia * rn)
+    }
+}
+
+/// Applies `impulse` to B and its opposite to A.
+fn apply_pair(
+    bodies: &mut [SolverBody],
+    a: usize,
+    b: usize,
+    impulse: Vec3<f32>,
+    r: [Vec3<f32>; 2],
+) {
+    bodies[a].apply_impulse(-impulse, r[0]);
+    bodies[b].apply_impulse(impulse, r[1]);
+}
+
+struct PointConstraint {
+    /// Contact point in the body spaces of A and B, to find it again in the next step.
+    local: [Vec3<f32>; 2],
+    /// Contact point relative to the centers of mass of A and B.
+    r: [Vec3<f32>; 2],
+    normal_mass: f32,
+    tangent_mass: [f32; 2],
+    /// Normal velocity the contact has to reach.
+    velocity_bias: f32,
+    /// Normal pseudo velocity that removes the penetration.
+    position_bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: [f32; 2],
+    pseudo_impulse: f32,
+}
+
+struct ManifoldConstraint {
+    a: usize,
+    b: usize,
+    normal: Vec3<f32>,
+    tangents: [Vec3<f32>; 2],
+    friction: f32,
+    points: Vec<PointConstraint>,
+}
+
+impl ManifoldConstraint {
+    fn tangent_impulse(&self, point: &PointConstraint) -> Vec3<f32> {
+        self.tangents[0] * point.tangent_impulse[0] + self.tangents[1] * point.tangent_impulse[1]
+    }
+}
+
+/// Sequential impulse (projected Gauss-Seidel) solver for contacts and joints.
+///
+/// Every contact point gets a non-penetration constraint along the contact normal and Coulomb
+/// friction along two tangents. The impulses are kept between steps for warm starting.
+///
+/// ```
+/// use std::sync::Arc;
+/// use rigid_body_physics_engine::body::RigidBody;
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::stl::IndexedMesh;
+/// use rigid_body_physics_engine::world::World;
+/// let cuboid = |half_extents| Arc::new(IndexedMesh::cuboid(half_extents));
+/// let mut world = World::new();
+/// let mut floor = RigidBody::new_static(cuboid(Vec3::new([5.0, 0.5, 5.0])));
+/// floor.position = Vec3::new([0.0, -0.5, 0.0]);
+/// world.add_body(floor);
+/// let mut cube = RigidBody::new(cuboid(Vec3::splat(0.5)), 1.0).unwrap();
+/// cube.position = Vec3::new([0.0, 1.0, 0.0]);
+/// let cube = world.add_body(cube);
+/// for _ in 0..120 {
+///     world.step(world.timestep);
+/// }
+/// let cube = world.body(cube);
+/// assert!((cube.position.y() - 0.5).abs() < 0.02);
+/// assert!(cube.linear_velocity.length() < 0.05);
+/// ```
+#[derive(Default)]
+pub struct ContactSolver {
+    manifolds: Vec<ManifoldConstraint>,
+}
+
+impl ContactSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves `contacts` and `joints` by applying impulses to the velocities of `bodies`, and
+    /// with [PositionCorrection::SplitImpulse] moves penetrating bodies out of each other.
+    /// Joints always correct their drift with Baumgarte stabilization.
+    ///
+    /// Call it before integrating: the constraints are solved for the velocities the bodies
+    /// will have after `acceleration` and their accumulated forces act on them for `dt`.
+    pub fn solve(
+        &mut self,
+        bodies: &mut [RigidBody],
+        contacts: &[Contact],
+        joints: &mut [Joint],
+        params: &SolverParams,
+        acceleration: Vec3<f32>,
+        dt: f32,
+    ) {
+        let mut initial: Vec<SolverBody> = bodies
+            .iter()
+            .map(|b| {
+                if b.is_sleeping() {
+                    // Nothing awake touches it, so it might as well be static.
+                    return SolverBody::fixed();
+                }
+                let (linear, angular) = if b.is_static() {
+                    (b.linear_velocity, b.angular_velocity)
+                } else {
+                    (
+                        b.linear_velocity + (acceleration + b.force * b.inv_mass) * dt,
+                        angular_velocity_step(b, b.orientation, b.angular_velocity, b.torque, dt),
+                    )
+                };
+                SolverBody {
+                    linear,
+                    angular,
+                    pseudo_linear: Vec3::ZERO,
+                    pseudo_angular: Vec3::ZERO,
+                    inv_mass: b.inv_mass,
+                    inv_inertia: b.world_inv_inertia(),
+                }
+            })
+            .collect();
+        // Stands in for the world in joints that have only one body.
+        initial.push(SolverBody::fixed());
+        let mut solver_bodies = initial.clone();
+
+        self.prepare(bodies, &solver_bodies, contacts, params, dt);
+        let mut joint_constraints: Vec<JointConstraint> = joints
+            .iter()
+            .map(|j| j.prepare(bodies, &solver_bodies, bodies.len(), params.baumgarte, dt))
+            .collect();
+        if params.warm_starting {
+            self.warm_start(&mut solver_bodies);
+            for c in &joint_constraints {
+                c.warm_start(&mut solver_bodies);
+            }
+        } else {
+            for c in &mut joint_constraints {
+                c.clear_impulses();
+            }
+        }
+        for _ in 0..params.velocity_iterations {
+            for c in &mut joint_constraints {
+                c.solve(&mut solver_bodies);
+            }
+            self.solve_velocities(&mut solver_bodies);
+        }
+        for (joint, c) in joints.iter_mut().zip(&joint_constraints) {
+            joint.store(c);
+        }
+        if params.position_correction == PositionCorrection::SplitImpulse {
+            for _ in 0..params.position_iterations {
+                self.solve_positions(&mut solver_bodies);
+            }
+        }
+
+        for ((body, solved), initial) in bodies.iter_mut().zip(&solver_bodies).zip(&initial) {
+            if body.is_static() || body.is_sleeping() {
+                continue;
+            }
+            body.linear_velocity += solved.linear - initial.linear;
+            body.angular_velocity += solved.angular - initial.angular;
+            body.position += solved.pseudo_linear * dt;
+            body.orientation = body.orientation.integrate(solved.pseudo_angular, dt);
+        }
+    }
+
+    /// Builds the constraints of this step and carries over the impulses of matching points
+    /// from the last one.
+    fn prepare(
+        &mut self,
+        bodies: &[RigidBody],
+        solver_bodies: &[SolverBody],
+        contacts: &[Contact],
+        params: &SolverParams,
+        dt: f32,
+    ) {
+        let previous = std::mem::take(&mut self.manifolds);
+        let mut by_pair: HashMap<(usize, usize), Vec<&ManifoldConstraint>> = HashMap::new();
+        for m in &previous {
+            by_pair.entry((m.a, m.b)).or_default().push(m);
+        }
+
+        for contact in contacts {
+            let (a, b) = (contact.a.0, contact.b.0);
+            let (body_a, body_b) = (&bodies[a], &bodies[b]);
+            let (sa, sb) = (&solver_bodies[a], &solver_bodies[b]);
+            let normal = contact.manifold.normal;
+            let t0 = normal.any_orthonormal();
+            let tangents = [t0, normal.cross(t0)];
+            let restitution = body_a.material.combined_restitution(&body_b.material);
+            let mut manifold = ManifoldConstraint {
+                a,
+                b,
+                normal,
+                tangents,
+                friction: body_a.material.combined_friction(&body_b.material),
+                points: Vec::with_capacity(contact.manifold.points.len()),
+            };
+            let (transform_a, transform_b) = (body_a.transform(), body_b.transform());
+
+            for p in &contact.manifold.points {
+                let point = (p.point_a + p.point_b) * 0.5;
+                let r = [point - body_a.position, point - body_b.position];
+                let inv_mass =
+                    |dir| sa.inv_effective_mass(r[0], dir) + sb.inv_effective_mass(r[1], dir);
+                let mass = |dir| {
+                    let k = inv_mass(dir);
+                    if k > 0.0 {
+                        1.0 / k
+                    } else {
+                        0.0
+                    }
+                };
+                let vn = (sb.velocity_at(r[1]) - sa.velocity_at(r[0])).dot(normal);
+
+                let mut velocity_bias = 0.0;
+                let mut position_bias = 0.0;
+                if p.depth < 0.0 {
+                    // Speculative contact, the gap may close within this step but not more.
+                    velocity_bias = p.depth / dt;
+                } else {
+                    let correction =
+                        params.baumgarte / dt * (p.depth - params.allowed_penetration).max(0.0);
+                    match params.position_correction {
+                        PositionCorrection::Baumgarte => velocity_bias = correction,
+                        PositionCorrection::SplitImpulse => position_bias = correction,
+                    }
+                }
+                // Bounce if the bodies are fast and will touch within this step.
+                if vn < -params.restitution_threshold && p.depth - vn * dt > 0.0 {
+                    velocity_bias = velocity_bias.max(-restitution * vn);
+                }
+
+                let mut constraint = PointConstraint {
+                    local: [
+                        transform_a.inverse_transform_point(point),
+                        transform_b.inverse_transform_point(point),
+                    ],
+                    r,
+                    normal_mass: mass(normal),
+                    tangent_mass: tangents.map(mass),
+                    velocity_bias,
+                    position_bias,
+                    normal_impulse: 0.0,
+                    tangent_impulse: [0.0; 2],
+                    pseudo_impulse: 0.0,
+                };
+                let matching = by_pair.get(&(a, b)).and_then(|old| {
+                    old.iter().find_map(|m| {
+                        let p = m.points.iter().find(|old| {
+                            old.local[0].distance(constraint.local[0]) < WARM_START_DISTANCE
+                                && old.local[1].distance(constraint.local[1]) < WARM_START_DISTANCE
+                        })?;
+                        Some((m, p))
+                    })
+                });
+                if let Some((m, old)) = matching.filter(|_| params.warm_starting) {
+                    constraint.normal_impulse = old.normal_impulse * m.normal.dot(normal).max(0.0);
+                    let tangent = m.tangent_impulse(old);
+                    constraint.tangent_impulse = tangents.map(|t| tangent.dot(t));
+                }
+                manifold.points.push(constraint);
+            }
+            self.manifolds.push(manifold);
+        }
+    }
+
+    fn warm_start(&self, bodies: &mut [SolverBody]) {
+        for m in &self.manifolds {
+            for p in &m.points {
+                let impulse = m.normal * p.normal_impulse + m.tangent_impulse(p);
+                apply_pair(bodies, m.a, m.b, impulse, p.r);
+            }
+        }
+    }
+
+    fn solve_velocities(&mut self, bodies: &mut [SolverBody]) {
+        for m in &mut self.manifolds {
+            let (a, b) = (m.a, m.b);
+            for p in &mut m.points {
+                // Friction first, so the normal constraint has the last word.
+                let dv = bodies[b].velocity_at(p.r[1]) - bodies[a].velocity_at(p.r[0]);
+                let old = p.tangent_impulse;
+                for i in 0..2 {
+                    p.tangent_impulse[i] -= dv.dot(m.tangents[i]) * p.tangent_mass[i];
+                }
+                // Clamp to the friction cone, approximated by a circle in the tangent plane.
+                let max = m.friction * p.normal_impulse;
+                let length = p.tangent_impulse[0].hypot(p.tangent_impulse[1]);
+                if length > max {
+                    p.tangent_impulse = p.tangent_impulse.map(|t| t * max / length);
+                }
+                let impulse = m.tangents[0] * (p.tangent_impulse[0] - old[0])
+                    + m.tangents[1] * (p.tangent_impulse[1] - old[1]);
+                apply_pair(bodies, a, b, impulse, p.r);
+
+                let vn =
+                    (bodies[b].velocity_at(p.r[1]) - bodies[a].velocity_at(p.r[0])).dot(m.normal);
+                let old = p.normal_impulse;
+                p.normal_impulse = (old + p.normal_mass * (p.velocity_bias - vn)).max(0.0);
+                apply_pair(bodies, a, b, m.normal * (p.normal_impulse - old), p.r);
+            }
+        }
+    }
+
+    fn solve_positions(&mut self, bodies: &mut [SolverBody]) {
+        for m in &mut self.manifolds {
+            let (a, b) = (m.a, m.b);
+            for p in &mut m.points {
+                let vn = (bodies[b].pseudo_velocity_at(p.r[1])
+                    - bodies[a].pseudo_velocity_at(p.r[0]))
+                .dot(m.normal);
+                let old = p.pseudo_impulse;
+                p.pseudo_impulse = (old + p.normal_mass * (p.position_bias - vn)).max(0.0);
+                let impulse = m.normal * (p.pseudo_impulse - old);
+                bodies[a].apply_pseudo_impulse(-impulse, p.r[0]);
+                bodies[b].apply_pseudo_impulse(impulse, p.r[1]);
+            }
+        }
+    }
+}
@@ -4,31 +4,7 @@ use std::io::BufRead;
 use std::io::BufWriter;
 use std::io::{BufReader, Read, Result, Write};
 
-static DEFAULT_EPSILON: f32 = 1e-6;
-
-#[derive(Default, Debug, Clone, Copy)]
-pub struct Vec3<F>([F; 3]);
-
-impl<F> Vec3<F> {
-    /// Constructor from array.
-    pub fn new(v: [F; 3]) -> Self {
-        Self(v)
-    }
-}
-
-impl<F> From<Vec3<F>> for [F; 3] {
-    fn from(v: Vec3<F>) -> Self {
-        v.0
-    }
-}
-
-impl<F> std::ops::Index<usize> for Vec3<F> {
-    type Output = F;
-    fn index(&self, i: usize) -> &Self::Output {
-        assert!(i < 3);
-        &self.0[i]
-    }
-}
+pub use crate::math::Vec3;
 
 // triangle corners
 pub type Vertex = Vec3<f32>;
@@ -42,47 +18,9 @@ pub struct Triangle {
     pub vertices: [Vertex; 3],
 }
 
-/*
-impl<F> Eq for Vec3<F> {
-    fn assert_receiver_is_total_eq(&self) {
-
-    }
-}
-*/
-
-macro_rules! eq_e {
-    ($v1:expr, $v2:expr, $ep:expr) => {
-        ($v2 - $v2).abs() < $ep
-    };
-}
-
-impl PartialEq for Vec3<f32> {
-    fn eq(&self, other: &Self) -> bool {
-        eq_e!(self[0], other[0], DEFAULT_EPSILON)
-            && eq_e!(self[1], other[1], DEFAULT_EPSILON)
-            && eq_e!(self[2], other[2], DEFAULT_EPSILON)
-    }
-}
-
 #[inline(always)]
 fn tri_area(a: Vertex, b: Vertex, c: Vertex) -> f32 {
-    fn cross(a: Vertex, b: Vertex) -> Vertex {
-        let x = a[1] * b[2] - a[2] * b[1];
-        let y = a[2] * b[0] - a[0] * b[2];
-        let z = a[0] * b[1] - a[1] * b[0];
-        Vertex::new([x, y, z])
-    }
-    fn sub(a: Vertex, b: Vertex) -> Vertex {
-        let x = a[0] - b[0];
-        let y = a[1] - b[1];
-        let z = a[2] - b[2];
-        Vertex::new([x, y, z])
-    }
-    fn length(v: Vertex) -> f32 {
-        (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
-    }
-
-    length(cross(sub(c, b), sub(a, b))) * 0.5
+    (c - b).cross(a - b).length() * 0.5
 }
 
 /// STL Triangle in indexed form, consisting of a normal and three indices to vertices in the
@@ -154,22 +92,247 @@ impl IndexedMesh {
             Ok(())
         }
     }
+
+    /// Iterates over the faces as [Triangle]s, e.g. to pass the mesh to [write_stl].
+    pub fn triangles(&self) -> impl ExactSizeIterator<Item = Triangle> + '_ {
+        self.faces.iter().map(|f| Triangle {
+            normal: f.normal,
+            vertices: f.vertices.map(|i| self.vertices[i]),
+        })
+    }
     // TODO load from mesh here
 }
 
+/// Bytes of the header at the start of a binary STL.
+const HEADER_LEN: usize = 80;
+
+/// Everything an STL file holds besides its triangles.
+///
+/// Binary STL has a free form header and an attribute word at the end of every face, which many
+/// exporters use for colors, see [ColorConvention]. Ascii STL has a name after `solid`.
+///
+/// ```
+/// use rigid_body_physics_engine::shape::Shape;
+/// use rigid_body_physics_engine::stl::{self, StlMetadata};
+/// let mesh = Shape::Sphere { radius: 1.0 }.to_mesh(8);
+/// let mut metadata = StlMetadata::new("ball");
+/// metadata.set_face_color(0, Some([255, 0, 0]));
+/// let mut bytes = Vec::new();
+/// stl::write_stl_with_metadata(&mut bytes, mesh.triangles(), &metadata).unwrap();
+///
+/// let (_, read) = stl::read_stl_with_metadata(&mut std::io::Cursor::new(bytes)).unwrap();
+/// assert_eq!(read.solid_name, "ball");
+/// assert_eq!(read.face_color(0), Some([255, 0, 0]));
+/// assert_eq!(read.face_color(1), None);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StlMetadata {
+    /// Header of binary STL, zeros for ascii STL.
+    pub header: [u8; HEADER_LEN],
+    /// Name after `solid` in ascii STL, or the text at the start of a binary header.
+    pub solid_name: String,
+    /// Attribute word of every face in order, empty for ascii STL. Missing words are written as
+    /// zero.
+    pub attributes: Vec<u16>,
+}
+
+impl Default for StlMetadata {
+    fn default() -> Self {
+        Self {
+            header: [0; HEADER_LEN],
+            solid_name: String::new(),
+            attributes: Vec::new(),
+        }
+    }
+}
+
+impl StlMetadata {
+    /// Creates metadata named `solid_name`, which is also written as the binary header text.
+    /// Names longer than the header are cut at the last character that fits.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::stl::StlMetadata;
+    /// let name = format!("{}é", "a".repeat(79));
+    /// let metadata = StlMetadata::new(&name);
+    /// assert_eq!(metadata.header[..79], name.as_bytes()[..79]);
+    /// assert_eq!(metadata.header[79], 0);
+    /// ```
+    pub fn new(solid_name: &str) -> Self {
+        let mut header = [0; HEADER_LEN];
+        let len = (0..=solid_name.len().min(HEADER_LEN))
+            .rev()
+            .find(|&i| solid_name.is_char_boundary(i))
+            .unwrap_or(0);
+        header[..len].copy_from_slice(&solid_name.as_bytes()[..len]);
+        Self {
+            header,
+            solid_name: solid_name.to_string(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Metadata of a binary STL with `header`. Its text up to the first zero byte becomes the
+    /// solid name, without the keyword `solid` that some exporters start it with.
+    fn from_header(header: [u8; HEADER_LEN]) -> Self {
+        let text = header.split(|&c| c == 0).next().unwrap_or_default();
+        let text = if is_ascii_header(text) {
+            &text.trim_ascii_start()[5..]
+        } else {
+            text
+        };
+        let solid_name = match std::str::from_utf8(text) {
+            Ok(text) if text.chars().all(|c| !c.is_control() || c.is_whitespace()) => {
+                text.trim().to_string()
+            }
+            _ => String::new(),
+        };
+        Self {
+            header,
+            solid_name,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Color convention of the attribute words. Materialise Magics marks its files with
+    /// `COLOR=` in the header, everything else is assumed to follow VisCAM and SolidView.
+    pub fn color_convention(&self) -> ColorConvention {
+        if self.find_header(MATERIALISE_COLOR).is_some() {
+            ColorConvention::Materialise
+        } else {
+            ColorConvention::VisCam
+        }
+    }
+
+    /// RGBA color of the whole object, following `COLOR=` in a Materialise header.
+    pub fn object_color(&self) -> Option<[u8; 4]> {
+        let at = self.find_header(MATERIALISE_COLOR)? + MATERIALISE_COLOR.len();
+        let color = self.header.get(at..at + 4)?;
+        Some([color[0], color[1], color[2], color[3]])
+    }
+
+    /// RGB color of face `face`, or `None` if it has no color. Materialise faces without their
+    /// own color have the [object color](StlMetadata::object_color).
+    pub fn face_color(&self, face: usize) -> Option<[u8; 3]> {
+        let word = *self.attributes.get(face)?;
+        let convention = self.color_convention();
+        match convention.decode(word) {
+            None if convention == ColorConvention::Materialise && word & COLOR_FLAG != 0 => {
+                self.object_color().map(|[r, g, b, _]| [r, g, b])
+            }
+            color => color,
+        }
+    }
+
+    /// Sets the attribute word of face `face` to `color` in the
+    /// [color convention](StlMetadata::color_convention) of the header, adding uncolored words
+    /// for the faces before it if needed.
+    pub fn set_face_color(&mut self, face: usize, color: Option<[u8; 3]>) {
+        let convention = self.color_convention();
+        if self.attributes.len() <= face {
+            self.attributes.resize(face + 1, convention.encode(None));
+        }
+        self.attributes[face] = convention.encode(color);
+    }
+
+    fn find_header(&self, needle: &[u8]) -> Option<usize> {
+        self.header.windows(needle.len()).position(|w| w == needle)
+    }
+}
+
+/// Marks the object color in the header of Materialise Magics.
+const MATERIALISE_COLOR: &[u8] = b"COLOR=";
+/// Bit 15 of an attribute word, which says whether its color is used.
+const COLOR_FLAG: u16 = 0x8000;
+
+/// How the attribute word of a binary STL face holds a 15 bit color, five bits per channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorConvention {
+    /// VisCAM and SolidView: blue in the lowest bits, then green and red, and bit 15 set if the
+    /// color is valid.
+    VisCam,
+    /// Materialise Magics: red in the lowest bits, then green and blue, and bit 15 clear if the
+    /// face has its own color instead of the object color.
+    Materialise,
+}
+
+impl ColorConvention {
+    /// Decodes the color of an attribute word to 8 bits per channel, `None` if the word has no
+    /// color of its own.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::stl::ColorConvention;
+    /// assert_eq!(ColorConvention::VisCam.decode(0x801f), Some([0, 0, 255]));
+    /// assert_eq!(ColorConvention::VisCam.decode(0x001f), None);
+    /// assert_eq!(ColorConvention::Materialise.decode(0x001f), Some([255, 0, 0]));
+    /// ```
+    pub fn decode(self, word: u16) -> Option<[u8; 3]> {
+        let valid = match self {
+            ColorConvention::VisCam => word & COLOR_FLAG != 0,
+            ColorConvention::Materialise => word & COLOR_FLAG == 0,
+        };
+        if !valid {
+            return None;
+        }
+        let channel = |shift: u16| {
+            let c = ((word >> shift) & 0x1f) as u8;
+            (c << 3) | (c >> 2)
+        };
+        let (low, mid, high) = (channel(0), channel(5), channel(10));
+        Some(match self {
+            ColorConvention::VisCam => [high, mid, low],
+            ColorConvention::Materialise => [low, mid, high],
+        })
+    }
+
+    /// Encodes `color` into an attribute word, dropping the 3 lowest bits of every channel.
+    /// `None` gives a word without color.
+    pub fn encode(self, color: Option<[u8; 3]>) -> u16 {
+        let Some([r, g, b]) = color else {
+            return match self {
+                ColorConvention::VisCam => 0,
+                ColorConvention::Materialise => COLOR_FLAG,
+            };
+        };
+        let [low, mid, high] = match self {
+            ColorConvention::VisCam => [b, g, r],
+            ColorConvention::Materialise => [r, g, b],
+        }
+        .map(|c| (c >> 3) as u16);
+        let word = low | (mid << 5) | (high << 10);
+        match self {
+            ColorConvention::VisCam => word | COLOR_FLAG,
+            ColorConvention::Materialise => word,
+        }
+    }
+}
+
 /// Write to std::io::Write as documented in
 /// [Wikipedia](https://en.wikipedia.org/wiki/STL_(file_format)#Binary_STL).
 ///
 /// ```
-/// use stl_io::{Vertex, Normal};
-/// let mesh = [stl_io::Triangle { normal: Normal::new([1.0, 0.0, 0.0]),
+/// use rigid_body_physics_engine::stl::{self, NormalV, Vertex};
+/// let mesh = [stl::Triangle { normal: NormalV::new([1.0, 0.0, 0.0]),
 ///                                vertices: [Vertex::new([0.0, -1.0, 0.0]),
 ///                                           Vertex::new([0.0, 1.0, 0.0]),
 ///                                           Vertex::new([0.0, 0.0, 0.5])]}];
 /// let mut binary_stl = Vec::<u8>::new();
-/// stl_io::write_stl(&mut binary_stl, mesh.iter()).unwrap();
+/// stl::write_stl(&mut binary_stl, mesh.iter()).unwrap();
 /// ```
 pub fn write_stl<T, W, I>(writer: &mut W, mesh: I) -> Result<()>
+where
+    W: std::io::Write,
+    I: std::iter::ExactSizeIterator<Item = T>,
+    T: std::borrow::Borrow<Triangle>,
+{
+    write_stl_with_metadata(writer, mesh, &StlMetadata::default())
+}
+
+/// Writes binary STL like [write_stl], with the header and attribute words of `metadata`.
+pub fn write_stl_with_metadata<T, W, I>(
+    writer: &mut W,
+    mesh: I,
+    metadata: &StlMetadata,
+) -> Result<()>
 where
     W: std::io::Write,
     I: std::iter::ExactSizeIterator<Item = T>,
@@ -177,28 +340,190 @@ where
 {
     let mut writer = BufWriter::new(writer);
 
-    // Write 80 byte header
-    writer.write_all(&[0u8; 80])?;
-    writer.write(&u32::to_le_bytes(mesh.len() as u32))?;
-    for t in mesh {
+    writer.write_all(&metadata.header)?;
+    writer.write_all(&u32::to_le_bytes(mesh.len() as u32))?;
+    for (i, t) in mesh.enumerate() {
         let t = t.borrow();
         for f in &t.normal.0 {
-            writer.write(&f32::to_le_bytes(*f as f32))?;
+            writer.write_all(&f32::to_le_bytes(*f))?;
         }
         for &p in &t.vertices {
             for c in &p.0 {
-                writer.write(&f32::to_le_bytes(*c as f32))?;
+                writer.write_all(&f32::to_le_bytes(*c))?;
             }
         }
-        // Attribute byte count
-        writer.write(&u16::to_le_bytes(0))?;
+        let attribute = metadata.attributes.get(i).copied().unwrap_or(0);
+        writer.write_all(&u16::to_le_bytes(attribute))?;
+    }
+    writer.flush()
+}
+
+/// Writes ascii STL with every coordinate rounded to `precision` digits after the decimal point.
+///
+/// ```
+/// use rigid_body_physics_engine::stl::{self, NormalV, Vertex};
+/// let mesh = [stl::Triangle { normal: NormalV::new([0.0, 0.0, 1.0]),
+///                                vertices: [Vertex::new([0.0, 0.0, 0.0]),
+///                                           Vertex::new([1.0, 0.0, 0.0]),
+///                                           Vertex::new([0.0, 1.0 / 3.0, 0.0])]}];
+/// let mut ascii_stl = Vec::<u8>::new();
+/// stl::write_stl_ascii(&mut ascii_stl, mesh.iter(), "part", 3).unwrap();
+/// let text = String::from_utf8(ascii_stl).unwrap();
+/// assert!(text.starts_with("solid part\n  facet normal 0.000 0.000 1.000\n"));
+/// assert!(text.contains("      vertex 0.000 0.333 0.000\n"));
+/// assert!(text.ends_with("endsolid part\n"));
+/// ```
+pub fn write_stl_ascii<T, W, I>(
+    writer: &mut W,
+    mesh: I,
+    solid_name: &str,
+    precision: usize,
+) -> Result<()>
+where
+    W: std::io::Write,
+    I: std::iter::Iterator<Item = T>,
+    T: std::borrow::Borrow<Triangle>,
+{
+    if solid_name.contains(['\n', '\r']) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("solid name {:?} has a line break", solid_name),
+        ));
+    }
+    let mut writer = BufWriter::new(writer);
+    let vector = |v: &Vec3<f32>| {
+        format!(
+            "{:.*} {:.*} {:.*}",
+            precision, v.0[0], precision, v.0[1], precision, v.0[2]
+        )
+    };
+
+    writeln!(writer, "{}", format!("solid {}", solid_name).trim_end())?;
+    for t in mesh {
+        let t = t.borrow();
+        writeln!(writer, "  facet normal {}", vector(&t.normal))?;
+        writeln!(writer, "    outer loop")?;
+        for p in &t.vertices {
+            writeln!(writer, "      vertex {}", vector(p))?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
     }
+    writeln!(writer, "{}", format!("endsolid {}", solid_name).trim_end())?;
     writer.flush()
 }
 
+/// Writes an [IndexedMesh] as binary STL, see [write_stl].
+pub fn write_indexed_stl<W: std::io::Write>(writer: &mut W, mesh: &IndexedMesh) -> Result<()> {
+    write_stl(writer, mesh.triangles())
+}
+
+/// Writes an [IndexedMesh] as ascii STL, see [write_stl_ascii].
+///
+/// Meshes from [read_stl] come back unchanged when the precision keeps every coordinate.
+///
+/// ```
+/// use rigid_body_physics_engine::shape::Shape;
+/// use rigid_body_physics_engine::stl;
+/// let cube = Shape::Box { half_extents: stl::Vec3::new([0.5, 1.0, 1.5]) }.to_mesh(4);
+/// let mut binary = std::io::Cursor::new(Vec::new());
+/// stl::write_indexed_stl(&mut binary, &cube).unwrap();
+/// binary.set_position(0);
+/// let mesh = stl::read_stl(&mut binary).unwrap();
+///
+/// let mut ascii = std::io::Cursor::new(Vec::new());
+/// stl::write_indexed_stl_ascii(&mut ascii, &mesh, "cube", 6).unwrap();
+/// ascii.set_position(0);
+/// assert_eq!(stl::read_stl(&mut ascii).unwrap(), mesh);
+/// ```
+pub fn write_indexed_stl_ascii<W: std::io::Write>(
+    writer: &mut W,
+    mesh: &IndexedMesh,
+    solid_name: &str,
+    precision: usize,
+) -> Result<()> {
+    write_stl_ascii(writer, mesh.triangles(), solid_name, precision)
+}
+
+/// Encoding of an STL file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    Binary,
+}
+
+impl StlFormat {
+    /// Guesses the format of the STL at the current position of `read`, and seeks back there.
+    ///
+    /// Files are binary if their length is exactly the 84 + 50·n bytes the face count n in a
+    /// binary header asks for, even if the header starts with `solid` as many exporters write.
+    /// Otherwise they are ascii if they start with `solid` and their first bytes are text.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::stl::{self, StlFormat};
+    /// let mut bytes = b"solid exported by a CAD tool".to_vec();
+    /// bytes.resize(80, b' ');
+    /// bytes.extend(1u32.to_le_bytes());
+    /// bytes.extend([0u8; 50]);
+    /// let mut read = std::io::Cursor::new(bytes);
+    /// assert_eq!(StlFormat::detect(&mut read).unwrap(), StlFormat::Binary);
+    /// assert_eq!(stl::read_stl(&mut read).unwrap().faces.len(), 1);
+    /// // Forcing the wrong format fails.
+    /// read.set_position(0);
+    /// assert!(stl::read_stl_with_format(&mut read, StlFormat::Ascii).is_err());
+    /// ```
+    pub fn detect<R>(read: &mut R) -> Result<Self>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        let start = read.stream_position()?;
+        let len = read.seek(std::io::SeekFrom::End(0))? - start;
+        read.seek(std::io::SeekFrom::Start(start))?;
+        let mut header = Vec::with_capacity(DETECT_LEN as usize);
+        let maybe_read_error = read.by_ref().take(DETECT_LEN).read_to_end(&mut header);
+        read.seek(std::io::SeekFrom::Start(start))?;
+        maybe_read_error?;
+        if let Some(count) = header.get(80..84) {
+            let faces = u32::from_le_bytes([count[0], count[1], count[2], count[3]]);
+            if BINARY_HEADER_LEN + BINARY_FACE_LEN * faces as u64 == len {
+                return Ok(StlFormat::Binary);
+            }
+        }
+        let is_text = header
+            .iter()
+            .all(|c| c.is_ascii_graphic() || c.is_ascii_whitespace());
+        if is_ascii_header(&header) && is_text {
+            Ok(StlFormat::Ascii)
+        } else {
+            Ok(StlFormat::Binary)
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            StlFormat::Ascii => StlFormat::Binary,
+            StlFormat::Binary => StlFormat::Ascii,
+        }
+    }
+}
+
+/// Bytes [StlFormat::detect] looks at.
+const DETECT_LEN: u64 = 512;
+
+/// Returns true if `line` starts with the keyword `solid` of ascii STL.
+fn is_ascii_header(line: &[u8]) -> bool {
+    let line = line.trim_ascii_start();
+    line.strip_prefix(b"solid")
+        .is_some_and(|rest| rest.first().is_none_or(|c| c.is_ascii_whitespace()))
+}
+
 /// Attempts to read either ascii or binary STL from std::io::Read.
 ///
+/// The format comes from [StlFormat::detect]. If the file turns out not to be valid in that
+/// format it is read in the other one, and if that fails too the first error is returned.
+///
 /// ```
+/// use rigid_body_physics_engine::stl;
 /// let mut reader = std::io::Cursor::new(
 ///     b"solid foobar
 ///       facet normal 0.1 0.2 0.3
@@ -209,19 +534,56 @@ where
 ///           endloop
 ///       endfacet
 ///       endsolid foobar".to_vec());
-/// let mesh = stl_io::read_stl(&mut reader).unwrap();
+/// let mesh = stl::read_stl(&mut reader).unwrap();
 /// ```
 pub fn read_stl<R>(read: &mut R) -> Result<IndexedMesh>
 where
     R: std::io::Read + std::io::Seek,
 {
-    create_stl_reader(read)?.as_indexed_triangles()
+    read_stl_with_metadata(read).map(|(mesh, _)| mesh)
+}
+
+/// Reads STL like [read_stl], together with its [StlMetadata].
+pub fn read_stl_with_metadata<R>(read: &mut R) -> Result<(IndexedMesh, StlMetadata)>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let start = read.stream_position()?;
+    let format = StlFormat::detect(read)?;
+    let error = match read_with_metadata(read, format) {
+        Ok(read) => return Ok(read),
+        Err(e) => e,
+    };
+    read.seek(std::io::SeekFrom::Start(start))?;
+    read_with_metadata(read, format.other()).map_err(|_| error)
+}
+
+/// Reads STL in the given format from std::io::Read.
+pub fn read_stl_with_format<R>(read: &mut R, format: StlFormat) -> Result<IndexedMesh>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    read_with_metadata(read, format).map(|(mesh, _)| mesh)
+}
+
+fn read_with_metadata<R>(read: &mut R, format: StlFormat) -> Result<(IndexedMesh, StlMetadata)>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let mut triangles = create_stl_reader_with_format(read, format)?;
+    let mesh = triangles.as_indexed_triangles()?;
+    Ok((mesh, triangles.metadata().clone()))
 }
 
 /// Attempts to create a [TriangleIterator](trait.TriangleIterator.html) for either ascii or binary
 /// STL from std::io::Read.
 ///
+/// The format comes from [StlFormat::detect], with a fallback to the other format if the reader
+/// can not be created. Errors later in the file only show up while iterating, use [read_stl] to
+/// fall back on those as well.
+///
 /// ```
+/// use rigid_body_physics_engine::stl;
 /// let mut reader = std::io::Cursor::new(b"solid foobar
 /// facet normal 1 2 3
 ///     outer loop
@@ -231,7 +593,7 @@ where
 ///     endloop
 /// endfacet
 /// endsolid foobar".to_vec());
-/// let stl = stl_io::create_stl_reader(&mut reader).unwrap();
+/// let triangles = stl::create_stl_reader(&mut reader).unwrap();
 /// ```
 pub fn create_stl_reader<'a, R>(
     read: &'a mut R,
@@ -239,57 +601,170 @@ pub fn create_stl_reader<'a, R>(
 where
     R: std::io::Read + std::io::Seek,
 {
-    match AsciiStlReader::probe(read) {
-        Ok(()) => AsciiStlReader::create_triangle_iterator(read),
-        Err(_) => BinaryStlReader::create_triangle_iterator(read),
+    let start = read.stream_position()?;
+    let mut format = StlFormat::detect(read)?;
+    // The reader borrows `read` for as long as it lives, so probe first and create it again.
+    let probe = create_stl_reader_with_format(read, format).err();
+    if let Some(error) = probe {
+        read.seek(std::io::SeekFrom::Start(start))?;
+        if create_stl_reader_with_format(read, format.other()).is_err() {
+            return Err(error);
+        }
+        format = format.other();
+    }
+    read.seek(std::io::SeekFrom::Start(start))?;
+    create_stl_reader_with_format(read, format)
+}
+
+/// Creates a [TriangleIterator](trait.TriangleIterator.html) for STL in the given format.
+pub fn create_stl_reader_with_format<'a, R>(
+    read: &'a mut R,
+    format: StlFormat,
+) -> Result<Box<dyn TriangleIterator<Item = Result<Triangle>> + 'a>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    match format {
+        StlFormat::Ascii => AsciiStlReader::create_triangle_iterator(read),
+        StlFormat::Binary => BinaryStlReader::create_triangle_iterator(read),
+    }
+}
+
+/// Bytes of the header and the face count that start a binary STL.
+const BINARY_HEADER_LEN: u64 = 84;
+/// Bytes of one face of a binary STL: normal, three vertices and the attribute word.
+const BINARY_FACE_LEN: u64 = 50;
+
+/// Error inside the [std::io::Error] of a binary STL that is shorter than the face count in its
+/// header says.
+///
+/// ```
+/// use rigid_body_physics_engine::stl::{self, TruncatedStl};
+/// let mut bytes = vec![0u8; 80];
+/// bytes.extend(2u32.to_le_bytes());
+/// bytes.extend([0u8; 50 + 20]);
+/// let error = stl::create_stl_reader(&mut std::io::Cursor::new(bytes)).err().unwrap();
+/// let truncated = error.get_ref().unwrap().downcast_ref::<TruncatedStl>().unwrap();
+/// assert_eq!(truncated.faces, 2);
+/// assert_eq!((truncated.expected_len, truncated.len), (184, 154));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TruncatedStl {
+    /// Faces announced in the header.
+    pub faces: usize,
+    /// Length in bytes the file needs for these faces.
+    pub expected_len: u64,
+    /// Actual length in bytes.
+    pub len: u64,
+}
+
+impl std::fmt::Display for TruncatedStl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "binary STL is truncated: {} faces need {} bytes, found {}",
+            self.faces, self.expected_len, self.len
+        )
     }
 }
 
+impl std::error::Error for TruncatedStl {}
+
 /// Struct for binary STL reader.
+///
+/// Every face is one record of exactly 50 bytes, and the attribute word that ends it is kept,
+/// see [TriangleIterator::metadata].
+///
+/// ```
+/// use rigid_body_physics_engine::stl;
+/// let mut bytes = vec![0u8; 80];
+/// bytes.extend(1u32.to_le_bytes());
+/// for x in [0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+///     bytes.extend(x.to_le_bytes());
+/// }
+/// bytes.extend(0x801fu16.to_le_bytes());
+/// let mut read = std::io::Cursor::new(bytes);
+/// let mut triangles = stl::create_stl_reader(&mut read).unwrap();
+/// let mesh = triangles.as_indexed_triangles().unwrap();
+/// assert_eq!(mesh.vertices[1].x(), 1.0);
+/// assert_eq!(triangles.metadata().attributes, [0x801f]);
+/// ```
 pub struct BinaryStlReader<'a> {
     reader: Box<dyn std::io::Read + 'a>,
     index: usize,
     size: usize,
+    metadata: StlMetadata,
 }
 
 impl<'a> BinaryStlReader<'a> {
-    /// Factory to create a new BinaryStlReader from read.
-    pub fn create_triangle_iterator(
-        read: &'a mut dyn (std::io::Read),
-    ) -> Result<Box<dyn TriangleIterator<Item = Result<Triangle>> + 'a>> {
+    /// Factory to create a new BinaryStlReader from read, which has to be exactly as long as the
+    /// face count in its header says.
+    pub fn create_triangle_iterator<R>(
+        read: &'a mut R,
+    ) -> Result<Box<dyn TriangleIterator<Item = Result<Triangle>> + 'a>>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        let start = read.stream_position()?;
+        let len = read.seek(std::io::SeekFrom::End(0))? - start;
+        read.seek(std::io::SeekFrom::Start(start))?;
+        if len < BINARY_HEADER_LEN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "binary STL of {len} bytes is shorter than its {BINARY_HEADER_LEN} byte header"
+                ),
+            ));
+        }
         let mut reader = Box::new(BufReader::new(read));
-        reader.read_exact(&mut [0u8; 80])?;
-        let mut f32_buf = [0; 4];
-        reader.read(&mut f32_buf)?;
-        let num_faces: u32 = u32::from_le_bytes(f32_buf);
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        let mut count = [0u8; 4];
+        reader.read_exact(&mut count)?;
+        let num_faces = u32::from_le_bytes(count);
+        let expected_len = BINARY_HEADER_LEN + BINARY_FACE_LEN * num_faces as u64;
+        if len < expected_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                TruncatedStl {
+                    faces: num_faces as usize,
+                    expected_len,
+                    len,
+                },
+            ));
+        }
+        if len > expected_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "binary STL has {} bytes after its {num_faces} faces",
+                    len - expected_len
+                ),
+            ));
+        }
         Ok(Box::new(BinaryStlReader {
             reader,
             index: 0,
             size: num_faces as usize,
+            metadata: StlMetadata::from_header(header),
         })
             as Box<dyn TriangleIterator<Item = Result<Triangle>>>)
     }
 
     fn next_face(&mut self) -> Result<Triangle> {
-        let mut normal = NormalV::default();
-        for f in &mut normal.0 {
-            let mut f32_buf = [0; 4];
-            self.reader.read(&mut f32_buf)?;
-            *f = f32::from_le_bytes(f32_buf);
-        }
-        let mut face = [Vertex::default(); 3];
-        for vertex in &mut face {
-            for c in vertex.0.iter_mut() {
-                let mut f32_buf = [0; 4];
-                self.reader.read(&mut f32_buf)?;
-                *c = f32::from_le_bytes(f32_buf);
-            }
-        }
-        let mut u16_buf = [0; 4];
-        self.reader.read(&mut u16_buf)?;
+        let mut record = [0u8; BINARY_FACE_LEN as usize];
+        self.reader.read_exact(&mut record)?;
+        let float = |i: usize| {
+            let at = 4 * i;
+            f32::from_le_bytes([record[at], record[at + 1], record[at + 2], record[at + 3]])
+        };
+        let vector = |i: usize| Vec3::new([float(i), float(i + 1), float(i + 2)]);
+        self.metadata
+            .attributes
+            .push(u16::from_le_bytes([record[48], record[49]]));
         Ok(Triangle {
-            normal,
-            vertices: face,
+            normal: vector(0),
+            vertices: [vector(3), vector(6), vector(9)],
         })
     }
 }
@@ -299,7 +774,12 @@ impl<'a> std::iter::Iterator for BinaryStlReader<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < self.size {
             self.index += 1;
-            return Some(self.next_face());
+            let face = self.next_face();
+            if face.is_err() {
+                // The records after a failed read are misaligned, do not return them.
+                self.index = self.size;
+            }
+            return Some(face);
         }
         None
     }
@@ -310,9 +790,13 @@ impl<'a> std::iter::Iterator for BinaryStlReader<'a> {
 
 /// Iterates over all Triangles in a STL.
 pub trait TriangleIterator: std::iter::Iterator<Item = Result<Triangle>> {
+    /// Header and name of the STL, and the attribute words of the faces read so far.
+    fn metadata(&self) -> &StlMetadata;
+
     /// Consumes this iterator and generates an [indexed Mesh](struct.IndexedMesh.html).
     ///
     /// ```
+    /// use rigid_body_physics_engine::stl;
     /// let mut reader = std::io::Cursor::new(b"solid foobar
     /// facet normal 1 2 3
     ///     outer loop
@@ -322,8 +806,8 @@ pub trait TriangleIterator: std::iter::Iterator<Item = Result<Triangle>> {
     ///     endloop
     /// endfacet
     /// endsolid foobar".to_vec());
-    /// let mut stl = stl_io::create_stl_reader(&mut reader).unwrap();
-    /// let indexed_mesh = stl.as_indexed_triangles().unwrap();
+    /// let mut triangles = stl::create_stl_reader(&mut reader).unwrap();
+    /// let indexed_mesh = triangles.as_indexed_triangles().unwrap();
     /// ```
     fn as_indexed_triangles(&mut self) -> Result<IndexedMesh> {
         let mut vertices = Vec::new();
@@ -362,10 +846,19 @@ pub trait TriangleIterator: std::iter::Iterator<Item = Result<Triangle>> {
 /// Struct for ascii STL reader.
 pub struct AsciiStlReader<'a> {
     lines: Box<dyn std::iter::Iterator<Item = Result<Vec<String>>> + 'a>,
+    metadata: StlMetadata,
 }
 
-impl<'a> TriangleIterator for BinaryStlReader<'a> {}
-impl<'a> TriangleIterator for AsciiStlReader<'a> {}
+impl<'a> TriangleIterator for BinaryStlReader<'a> {
+    fn metadata(&self) -> &StlMetadata {
+        &self.metadata
+    }
+}
+impl<'a> TriangleIterator for AsciiStlReader<'a> {
+    fn metadata(&self) -> &StlMetadata {
+        &self.metadata
+    }
+}
 
 impl<'a> std::iter::Iterator for AsciiStlReader<'a> {
     type Item = Result<Triangle>;
@@ -386,10 +879,10 @@ impl<'a> AsciiStlReader<'a> {
         // Try to seek back to start before evaluating potential read errors.
         read.seek(std::io::SeekFrom::Start(0))?;
         maybe_read_error?;
-        if !header.starts_with("solid ") {
+        if !is_ascii_header(header.as_bytes()) {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
-                "ascii STL does not start with \"solid \"",
+                "ascii STL does not start with \"solid\"",
             ))
         } else {
             Ok(())
@@ -397,15 +890,15 @@ impl<'a> AsciiStlReader<'a> {
     }
     /// Factory to create a new ascii STL Reader from read.
     pub fn create_triangle_iterator(
-        read: &'a mut dyn (std::io::Read),
+        read: &'a mut dyn std::io::Read,
     ) -> Result<Box<dyn TriangleIterator<Item = Result<Triangle>> + 'a>> {
         let mut lines = BufReader::new(read).lines();
-        match lines.next() {
+        let solid_name = match lines.next() {
             Some(Err(e)) => return Err(e),
-            Some(Ok(ref line)) if !line.starts_with("solid ") => {
+            Some(Ok(ref line)) if !is_ascii_header(line.as_bytes()) => {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
-                    "ascii STL does not start with \"solid \"",
+                    "ascii STL does not start with \"solid\"",
                 ))
             }
             None => {
@@ -414,8 +907,8 @@ impl<'a> AsciiStlReader<'a> {
                     "empty file?",
                 ))
             }
-            _ => {}
-        }
+            Some(Ok(line)) => line.trim_start()[5..].trim().to_string(),
+        };
         let lines = lines
             .map(|result| {
                 result.map(|l| {
@@ -429,6 +922,10 @@ impl<'a> AsciiStlReader<'a> {
             .filter(|result| result.is_err() || (!result.as_ref().unwrap().is_empty()));
         Ok(Box::new(AsciiStlReader {
             lines: Box::new(lines),
+            metadata: StlMetadata {
+                solid_name,
+                ..StlMetadata::default()
+            },
         })
             as Box<dyn TriangleIterator<Item = Result<Triangle>>>)
     }
@@ -0,0 +1,278 @@
+use crate::broadphase::Aabb;
+use crate::math::Vec3;
+use crate::narrowphase::barycentric;
+use crate::stl::{IndexedMesh, Triangle};
+use gxhash::{HashMap, HashMapExt};
+use std::sync::Arc;
+
+/// Triangles per leaf of the bounding volume hierarchy.
+const LEAF_SIZE: usize = 4;
+/// Edges whose faces bend by less than this, as one minus the cosine of the angle, are flat.
+const FLAT_EDGE: f32 = 1e-4;
+/// Slack of the feature tests in [TriMeshCollider::is_admissible].
+const FEATURE_TOLERANCE: f32 = 1e-3;
+
+/// Shape of a triangle edge together with the face on its other side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edge {
+    /// No other face shares the edge, so it is a real edge of the surface.
+    Boundary,
+    /// The surface bends away from the normal here. `outward` lies in the plane of the other face,
+    /// perpendicular to the edge and pointing away from that face.
+    Convex { outward: Vec3<f32> },
+    /// Flat or concave edge, only the face normals push bodies away from it.
+    Internal,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    aabb: Aabb,
+    /// Children for inner nodes, range of `order` for leaves.
+    kind: NodeKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NodeKind {
+    Inner { left: usize, right: usize },
+    Leaf { start: usize, end: usize },
+}
+
+/// Static triangle soup, for example an STL of a fixture or terrain, that bodies collide with
+/// exactly instead of with its convex hull.
+///
+/// The triangles are one sided: bodies are always pushed out along the side their normal points
+/// to, which follows the winding of the vertices. Edges between two triangles that lie flat or
+/// bend inwards only push bodies along the face normals, so bodies sliding across them do not get
+/// caught on the seams.
+///
+/// ```
+/// use rigid_body_physics_engine::broadphase::Aabb;
+/// use rigid_body_physics_engine::math::Vec3;
+/// use rigid_body_physics_engine::stl::{IndexedMesh, IndexedTriangle};
+/// use rigid_body_physics_engine::trimesh::TriMeshCollider;
+/// use std::sync::Arc;
+/// // A 2x2 quad split into two triangles facing up.
+/// let mesh = IndexedMesh {
+///     vertices: vec![
+///         Vec3::new([-1.0, 0.0, -1.0]),
+///         Vec3::new([1.0, 0.0, -1.0]),
+///         Vec3::new([1.0, 0.0, 1.0]),
+///         Vec3::new([-1.0, 0.0, 1.0]),
+///     ],
+///     faces: [[0, 2, 1], [0, 3, 2]]
+///         .map(|vertices| IndexedTriangle { normal: Vec3::ZERO, vertices })
+///         .to_vec(),
+/// };
+/// let floor = TriMeshCollider::new(Arc::new(mesh));
+/// assert!(floor.triangle(0).normal.y() > 0.99);
+/// let mut hits = 0;
+/// floor.query(&Aabb::new(Vec3::splat(-0.1), Vec3::splat(0.1)), |_| hits += 1);
+/// assert_eq!(hits, 2);
+/// floor.query(&Aabb::new(Vec3::splat(0.5), Vec3::splat(1.0)), |_| hits += 1);
+/// assert_eq!(hits, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TriMeshCollider {
+    mesh: Arc<IndexedMesh>,
+    /// Unit normal of every face from its winding, the normals stored in STL files are often off.
+    normals: Vec<Vec3<f32>>,
+    /// Edge `i` of a face runs from its vertex `i` to vertex `i + 1`.
+    edges: Vec<[Edge; 3]>,
+    nodes: Vec<Node>,
+    /// Faces in the order the leaves refer to them.
+    order: Vec<usize>,
+}
+
+impl TriMeshCollider {
+    /// Builds the hierarchy over all faces of `mesh` that have an area. The mesh is shared, not
+    /// copied.
+    pub fn new(mesh: Arc<IndexedMesh>) -> Self {
+        let normals: Vec<Vec3<f32>> = mesh
+            .faces
+            .iter()
+            .map(|f| {
+                let [a, b, c] = f.vertices.map(|i| mesh.vertices[i]);
+                (b - a).cross(c - a).try_normalize().unwrap_or(Vec3::ZERO)
+            })
+            .collect();
+        let mut collider = Self {
+            edges: Vec::new(),
+            nodes: Vec::new(),
+            order: (0..mesh.faces.len())
+                .filter(|&i| normals[i] != Vec3::ZERO)
+                .collect(),
+            normals,
+            mesh,
+        };
+        collider.edges = collider.classify_edges();
+        if !collider.order.is_empty() {
+            collider.build(0, collider.order.len());
+        }
+        collider
+    }
+
+    /// The mesh the collider was built from.
+    pub fn mesh(&self) -> &IndexedMesh {
+        &self.mesh
+    }
+
+    /// Face `i` with the normal given by its winding.
+    pub fn triangle(&self, i: usize) -> Triangle {
+        Triangle {
+            normal: self.normals[i],
+            vertices: self.mesh.faces[i].vertices.map(|v| self.mesh.vertices[v]),
+        }
+    }
+
+    /// Bounding box of all faces in body space.
+    pub fn aabb(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |n| n.aabb)
+    }
+
+    /// Calls `f` with every face whose bounding box overlaps `aabb`, given in body space.
+    pub fn query(&self, aabb: &Aabb, mut f: impl FnMut(usize)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !node.aabb.overlaps(aabb) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Inner { left, right } => stack.extend([left, right]),
+                NodeKind::Leaf { start, end } => {
+                    for &face in &self.order[start..end] {
+                        if self.face_aabb(face).overlaps(aabb) {
+                            f(face);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if the surface can push a body touching face `face` at `point` in direction
+    /// `dir`, both in body space.
+    ///
+    /// Directions off the face normal are only admissible on boundary edges and on edges and
+    /// vertices where the surface bends away, everywhere else they come from the neighbouring
+    /// faces poking through and have to be replaced by the face normal.
+    pub fn is_admissible(&self, face: usize, point: Vec3<f32>, dir: Vec3<f32>) -> bool {
+        let normal = self.normals[face];
+        let Some(dir) = dir.try_normalize() else {
+            return false;
+        };
+        if dir.dot(normal) >= 1.0 - FEATURE_TOLERANCE {
+            return true;
+        }
+        if dir.dot(normal) < -FEATURE_TOLERANCE {
+            return false;
+        }
+        let corners = self.triangle(face).vertices;
+        let [a, b, c] = corners;
+        let weights = barycentric(point, a, b, c);
+        let mut on_edge = false;
+        for i in 0..3 {
+            // Edge `i` lies opposite of vertex `i + 2`.
+            if weights[(i + 2) % 3] > FEATURE_TOLERANCE {
+                continue;
+            }
+            on_edge = true;
+            let along = corners[(i + 1) % 3] - corners[i];
+            let outward = along.cross(normal).normalize();
+            let admissible = match self.edges[face][i] {
+                Edge::Boundary => true,
+                Edge::Convex { outward: other } => dir.dot(other) >= -FEATURE_TOLERANCE,
+                Edge::Internal => dir.dot(outward) <= FEATURE_TOLERANCE,
+            };
+            if !admissible {
+                return false;
+            }
+        }
+        on_edge
+    }
+
+    fn face_aabb(&self, face: usize) -> Aabb {
+        Aabb::from_points(self.triangle(face).vertices)
+    }
+
+    fn classify_edges(&self) -> Vec<[Edge; 3]> {
+        let faces = &self.mesh.faces;
+        let mut shared: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for &f in &self.order {
+            let v = faces[f].vertices;
+            for i in 0..3 {
+                let (a, b) = (v[i], v[(i + 1) % 3]);
+                shared.entry((a.min(b), a.max(b))).or_default().push(f);
+            }
+        }
+        (0..faces.len())
+            .map(|f| {
+                let v = faces[f].vertices;
+                std::array::from_fn(|i| {
+                    let (a, b) = (v[i], v[(i + 1) % 3]);
+                    let neighbours = shared.get(&(a.min(b), a.max(b)));
+                    // Edges shared by more than two faces are not a surface, keep them sharp.
+                    let Some(&[x, y]) = neighbours.map(Vec::as_slice) else {
+                        return Edge::Boundary;
+                    };
+                    let other = if x == f { y } else { x };
+                    self.classify_edge(f, other, a, b)
+                })
+            })
+            .collect()
+    }
+
+    fn classify_edge(&self, face: usize, other: usize, a: usize, b: usize) -> Edge {
+        let (normal, other_normal) = (self.normals[face], self.normals[other]);
+        let vertices = &self.mesh.vertices;
+        let opposite = self.mesh.faces[other]
+            .vertices
+            .into_iter()
+            .find(|&v| v != a && v != b)
+            .unwrap_or(a);
+        let p = vertices[opposite] - vertices[a];
+        if normal.dot(other_normal) >= 1.0 - FLAT_EDGE || p.dot(normal) >= 0.0 {
+            return Edge::Internal;
+        }
+        let along = (vertices[b] - vertices[a]).normalize();
+        // The in-plane direction of the other face across the edge, away from its interior.
+        let inward = p - along * p.dot(along);
+        Edge::Convex {
+            outward: -inward.normalize(),
+        }
+    }
+
+    /// Builds the subtree over `order[start..end]` by splitting at the median of the longest
+    /// axis of the face centroids, and returns its index.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let index = self.nodes.len();
+        let aabb = self.order[start..end]
+            .iter()
+            .fold(Aabb::EMPTY, |aabb, &f| aabb.union(&self.face_aabb(f)));
+        self.nodes.push(Node {
+            aabb,
+            kind: NodeKind::Leaf { start, end },
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+        let mesh = &self.mesh;
+        let centroid = |f: usize| {
+            let [a, b, c] = mesh.faces[f].vertices.map(|i| mesh.vertices[i]);
+            (a + b + c) / 3.0
+        };
+        let bounds = Aabb::from_points(self.order[start..end].iter().map(|&f| centroid(f)));
+        let axis = bounds.extents().max_axis();
+        let mid = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(mid - start, |&x, &y| {
+            centroid(x)[axis].total_cmp(&centroid(y)[axis])
+        });
+        let left = self.build(start, mid);
+        let right = self.build(mid, end);
+        self.nodes[index].kind = NodeKind::Inner { left, right };
+        index
+    }
+}
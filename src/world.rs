@@ -0,0 +1,448 @@
+use crate::body::RigidBody;
+use crate::broadphase::{Broadphase, DynamicAabbTree};
+use crate::ccd::{time_of_impact, Motion};
+use crate::integrator::{Integrator, SymplecticEuler};
+use crate::island::{self, SleepParams};
+use crate::joint::{Joint, JointHandle, JointKind};
+use crate::math::{Transform, Vec3};
+use crate::narrowphase::{self, Contact};
+use crate::solver::{ContactSolver, SolverParams};
+
+/// Index of a body inside a [World].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle(pub usize);
+
+/// Owns all bodies and advances the simulation.
+///
+/// The simulation runs at a fixed `timestep` no matter how often [World::advance] is called, so
+/// results do not depend on the frame rate of whoever drives it.
+///
+/// ```
+/// use rigid_body_physics_engine::world::World;
+/// let mut world = World::new();
+/// // A slow frame is split into several fixed steps.
+/// let steps = world.advance(2.5 * world.timestep);
+/// assert_eq!(steps, 2);
+/// assert!((world.alpha() - 0.5).abs() < 1e-4);
+/// ```
+pub struct World {
+    /// Acceleration applied to every dynamic body.
+    pub gravity: Vec3<f32>,
+    /// Length of one simulation step in seconds.
+    pub timestep: f32,
+    /// Upper bound of steps taken by a single [World::advance], time beyond that is dropped so
+    /// that a slow frame can not snowball into ever slower frames.
+    pub max_substeps: usize,
+    /// Shapes closer than this generate speculative contacts before they touch.
+    pub contact_margin: f32,
+    /// Bodies with [RigidBody::ccd] set are swept once they move further than this fraction of
+    /// their thinnest extent within one step.
+    pub ccd_threshold: f32,
+    /// Upper bound of impacts resolved for one body within one step.
+    pub max_ccd_substeps: usize,
+    /// Settings of the contact solver.
+    pub solver: SolverParams,
+    /// When resting bodies are put to sleep.
+    pub sleep: SleepParams,
+    integrator: Box<dyn Integrator>,
+    broadphase: Box<dyn Broadphase>,
+    pairs: Vec<(BodyHandle, BodyHandle)>,
+    contacts: Vec<Contact>,
+    contact_solver: ContactSolver,
+    joints: Vec<Joint>,
+    islands: Vec<Vec<BodyHandle>>,
+    bodies: Vec<RigidBody>,
+    previous: Vec<Transform<f32>>,
+    accumulator: f32,
+    time: f64,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    /// Creates an empty world with earth gravity along -Y, stepping at 60 Hz with
+    /// [SymplecticEuler].
+    pub fn new() -> Self {
+        Self::with_integrator(SymplecticEuler)
+    }
+
+    /// Creates an empty world like [World::new] that integrates bodies with `integrator`.
+    ///
+    /// ```
+    /// use rigid_body_physics_engine::integrator::RungeKutta4;
+    /// use rigid_body_physics_engine::world::World;
+    /// let world = World::with_integrator(RungeKutta4);
+    /// ```
+    pub fn with_integrator(integrator: impl Integrator + 'static) -> Self {
+        Self {
+            gravity: Vec3::new([0.0, -9.81, 0.0]),
+            timestep: 1.0 / 60.0,
+            max_substeps: 8,
+            contact_margin: 0.02,
+            ccd_threshold: 0.5,
+            max_ccd_substeps: 4,
+            solver: SolverParams::default(),
+            sleep: SleepParams::default(),
+            integrator: Box::new(integrator),
+            broadphase: Box::new(DynamicAabbTree::default()),
+            pairs: Vec::new(),
+            contacts: Vec::new(),
+            contact_solver: ContactSolver::new(),
+            joints: Vec::new(),
+            islands: Vec::new(),
+            bodies: Vec::new(),
+            previous: Vec::new(),
+            accumulator: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Replaces the broadphase, for example with a
+    /// [SweepAndPrune](crate::broadphase::SweepAndPrune).
+    pub fn set_broadphase(&mut self, mut broadphase: impl Broadphase + 'static) {
+        for (handle, body) in self.iter() {
+            broadphase.insert(handle, body.aabb());
+        }
+        self.broadphase = Box::new(broadphase);
+    }
+
+    /// Adds a body and returns its handle.
+    pub fn add_body(&mut self, body: RigidBody) -> BodyHandle {
+        let handle = BodyHandle(self.bodies.len());
+        self.broadphase.insert(handle, body.aabb());
+        self.previous.push(body.transform());
+        self.bodies.push(body);
+        handle
+    }
+
+    /// Connects body `a` with body `b`, or with the world if `b` is `None`, through a joint placed
+    /// at the world space `frame`. Both bodies keep their current relative placement.
+    pub fn add_joint(
+        &mut self,
+        kind: JointKind,
+        a: BodyHandle,
+        b: Option<BodyHandle>,
+        frame: Transform<f32>,
+    ) -> JointHandle {
+        let frame_a = self.bodies[a.0].transform().inverse() * frame;
+        let frame_b = match b {
+            Some(b) => self.bodies[b.0].transform().inverse() * frame,
+            None => frame,
+        };
+        self.insert_joint(Joint::new(kind, a, frame_a, b, frame_b))
+    }
+
+    /// Adds a joint whose frames are already in body space, for example a
+    /// [JointKind::Distance] with separate anchors on the two bodies.
+    pub fn insert_joint(&mut self, joint: Joint) -> JointHandle {
+        self.joints.push(joint);
+        JointHandle(self.joints.len() - 1)
+    }
+
+    pub fn joint(&self, handle: JointHandle) -> &Joint {
+        &self.joints[handle.0]
+    }
+
+    pub fn joint_mut(&mut self, handle: JointHandle) -> &mut Joint {
+        &mut self.joints[handle.0]
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    /// Candidate pairs the broadphase found in the last step, without pairs that have no awake
+    /// dynamic body and pairs of bodies joined by a joint that does not collide them.
+    pub fn broadphase_pairs(&self) -> &[(BodyHandle, BodyHandle)] {
+        &self.pairs
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> &RigidBody {
+        &self.bodies[handle.0]
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> &mut RigidBody {
+        &mut self.bodies[handle.0]
+    }
+
+    pub fn bodies(&self) -> &[RigidBody] {
+        &self.bodies
+    }
+
+    /// Iterates over all bodies together with their handles.
+    pub fn iter(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.bodies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (BodyHandle(i), b))
+    }
+
+    /// Kinetic plus gravitational potential energy of all dynamic bodies, useful to compare the
+    /// drift of integrators.
+    pub fn total_energy(&self) -> f32 {
+        self.bodies
+            .iter()
+            .filter(|b| !b.is_static())
+            .map(|b| b.kinetic_energy() - b.mass * self.gravity.dot(b.position))
+            .sum()
+    }
+
+    /// Contacts found in the last step.
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Groups of dynamic bodies that touched or were joined in the last step.
+    pub fn islands(&self) -> &[Vec<BodyHandle>] {
+        &self.islands
+    }
+
+    /// Simulated time in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Advances the simulation by exactly `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        for (previous, body) in self.previous.iter_mut().zip(&self.bodies) {
+            *previous = body.transform();
+        }
+        let awake = |b: &RigidBody| !b.is_static() && !b.is_sleeping();
+        for (i, body) in self.bodies.iter().enumerate() {
+            if awake(body) {
+                self.broadphase.update(BodyHandle(i), body.aabb());
+            }
+        }
+        let joined = self.joined_pairs();
+        let bodies = &self.bodies;
+        self.pairs = self.broadphase.pairs();
+        // Pairs without an awake body can not have changed since the last step.
+        self.pairs.retain(|&(a, b)| {
+            (awake(&bodies[a.0]) || awake(&bodies[b.0])) && !joined.contains(&(a, b))
+        });
+        let margin = self.contact_margin;
+        self.contacts = self
+            .pairs
+            .iter()
+            .flat_map(|&(a, b)| {
+                narrowphase::collide(&bodies[a.0], &bodies[b.0], margin)
+                    .into_iter()
+                    .map(move |manifold| Contact { a, b, manifold })
+            })
+            .collect();
+
+        // Sleeping islands have no contacts, keep them together so they wake up as a whole.
+        let asleep = self
+            .islands
+            .iter()
+            .filter(|island| island.iter().any(|h| self.bodies[h.0].is_sleeping()))
+            .flat_map(|island| island.windows(2).map(|w| (w[0], w[1])));
+        let edges = self
+            .contacts
+            .iter()
+            .map(|c| (c.a, c.b))
+            .chain(
+                self.joints
+                    .iter()
+                    .filter_map(|j| Some((j.body_a, j.body_b?))),
+            )
+            .chain(asleep);
+        self.islands = island::islands(bodies.len(), edges, |i| !bodies[i].is_static());
+        // Touching an awake body wakes the whole island.
+        for island in &self.islands {
+            if island.iter().any(|&h| !self.bodies[h.0].is_sleeping()) {
+                for &h in island {
+                    if self.bodies[h.0].is_sleeping() {
+                        self.bodies[h.0].wake_up();
+                    }
+                }
+            }
+        }
+
+        self.contact_solver.solve(
+            &mut self.bodies,
+            &self.contacts,
+            &mut self.joints,
+            &self.solver,
+            self.gravity,
+            dt,
+        );
+        for body in self.bodies.iter_mut().filter(|b| awake(b)) {
+            self.integrator.integrate(body, self.gravity, dt);
+        }
+        for body in &mut self.bodies {
+            body.force = Vec3::ZERO;
+            body.torque = Vec3::ZERO;
+        }
+        self.solve_ccd(dt);
+        self.update_sleep(dt);
+        self.time += dt as f64;
+    }
+
+    /// Pairs of bodies joined by a joint that does not let them collide, smaller handle first.
+    fn joined_pairs(&self) -> Vec<(BodyHandle, BodyHandle)> {
+        self.joints
+            .iter()
+            .filter(|j| !j.collide_connected)
+            .filter_map(|j| {
+                let b = j.body_b?;
+                Some((j.body_a.min(b), j.body_a.max(b)))
+            })
+            .collect()
+    }
+
+    /// Sweeps fast bodies with [RigidBody::ccd] from where they started the step to where they
+    /// ended. A body that hits something is moved back to the time of impact, the impact is
+    /// resolved and the body moves on with its new velocity for the rest of the step.
+    fn solve_ccd(&mut self, dt: f32) {
+        let distance = self.contact_margin * 0.5;
+        let joined = self.joined_pairs();
+        for i in 0..self.bodies.len() {
+            let body = &self.bodies[i];
+            if !body.ccd || body.is_static() || body.is_sleeping() {
+                continue;
+            }
+            let radius = body.collider.bounding_radius();
+            let thickness = body
+                .collider
+                .aabb(&Transform::IDENTITY)
+                .extents()
+                .min_elem();
+            let mut start = self.previous[i];
+            let mut remaining = dt;
+            for substep in 0..self.max_ccd_substeps {
+                let body = &self.bodies[i];
+                let motion = Motion::between(start, body.transform());
+                if motion.max_travel(radius) <= self.ccd_threshold * thickness {
+                    break;
+                }
+                let swept = motion.swept_aabb(radius);
+                let hit = self
+                    .iter()
+                    .filter(|&(h, other)| {
+                        let pair = (h.min(BodyHandle(i)), h.max(BodyHandle(i)));
+                        h.0 != i && !joined.contains(&pair) && swept.overlaps(&other.aabb())
+                    })
+                    .filter_map(|(h, other)| {
+                        let still = Motion::fixed(other.transform());
+                        let t = time_of_impact(
+                            &body.collider,
+                            &motion,
+                            &other.collider,
+                            &still,
+                            distance,
+                        )?;
+                        Some((t, h))
+                    })
+                    .min_by(|x, y| x.0.total_cmp(&y.0));
+                let Some((t, other)) = hit else {
+                    break;
+                };
+
+                let pose = motion.at(t);
+                let body = &mut self.bodies[i];
+                body.position = pose.translation;
+                body.orientation = pose.rotation;
+                if self.bodies[other.0].is_sleeping() {
+                    self.bodies[other.0].wake_up();
+                }
+                // Only the two bodies take part, solve them on their own.
+                let (a, b) = (other.min(BodyHandle(i)), other.max(BodyHandle(i)));
+                let mut pair = [self.bodies[a.0].clone(), self.bodies[b.0].clone()];
+                let contacts: Vec<Contact> =
+                    narrowphase::collide(&pair[0], &pair[1], self.contact_margin)
+                        .into_iter()
+                        .map(|manifold| Contact {
+                            a: BodyHandle(0),
+                            b: BodyHandle(1),
+                            manifold,
+                        })
+                        .collect();
+                ContactSolver::new().solve(
+                    &mut pair,
+                    &contacts,
+                    &mut [],
+                    &self.solver,
+                    Vec3::ZERO,
+                    dt,
+                );
+                let [pair_a, pair_b] = pair;
+                self.bodies[a.0] = pair_a;
+                self.bodies[b.0] = pair_b;
+                if substep + 1 == self.max_ccd_substeps {
+                    break;
+                }
+
+                remaining *= 1.0 - t;
+                start = self.bodies[i].transform();
+                let body = &mut self.bodies[i];
+                body.position += body.linear_velocity * remaining;
+                body.orientation = body.orientation.integrate(body.angular_velocity, remaining);
+            }
+        }
+    }
+
+    /// Puts islands to sleep whose bodies all rested for long enough.
+    fn update_sleep(&mut self, dt: f32) {
+        let params = self.sleep;
+        if !params.enabled {
+            for body in &mut self.bodies {
+                body.wake_up();
+            }
+            return;
+        }
+        for island in &self.islands {
+            let mut can_sleep = true;
+            for &h in island {
+                let body = &mut self.bodies[h.0];
+                if body.is_sleeping() {
+                    continue;
+                }
+                let resting = body.linear_velocity.length() < params.linear_threshold
+                    && body.angular_velocity.length() < params.angular_threshold;
+                body.sleep_time = if resting { body.sleep_time + dt } else { 0.0 };
+                can_sleep &= body.sleep_time >= params.time_to_sleep;
+            }
+            if can_sleep {
+                for &h in island {
+                    let body = &mut self.bodies[h.0];
+                    body.sleeping = true;
+                    body.linear_velocity = Vec3::ZERO;
+                    body.angular_velocity = Vec3::ZERO;
+                }
+            }
+        }
+    }
+
+    /// Adds `frame_time` seconds to the accumulator and takes as many fixed steps as fit into it.
+    ///
+    /// Returns the number of steps taken.
+    pub fn advance(&mut self, frame_time: f32) -> usize {
+        self.accumulator += frame_time.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_substeps {
+            self.step(self.timestep);
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+        if steps == self.max_substeps {
+            self.accumulator = self.accumulator.min(self.timestep);
+        }
+        steps
+    }
+
+    /// How far the accumulator is into the next step, between 0 and 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.timestep).clamp(0.0, 1.0)
+    }
+
+    /// Transform of a body blended between the last two steps by [World::alpha], for rendering
+    /// between simulation steps.
+    pub fn interpolated_transform(&self, handle: BodyHandle) -> Transform<f32> {
+        self.previous[handle.0].lerp(&self.bodies[handle.0].transform(), self.alpha())
+    }
+}